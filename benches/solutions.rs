@@ -0,0 +1,279 @@
+//! Benchmarks of every solution on its checked-in input and of the IntCode VM.
+//!
+//! ```text
+//! cargo bench --bench solutions -- [filter] [--save-baseline <name>] [--baseline <name>] [--threshold <percent>]
+//!     --save-baseline <name>  store the measures in target/bench/<name>.txt
+//!     --baseline <name>       compare the measures with a stored baseline
+//!     --threshold <percent>   change reported as a regression, 10 by default
+//!     --budget <ms>           time spent sampling each benchmark, 1000 by default
+//! ```
+//!
+//! Every benchmark is sampled until the budget is spent and the median is kept,
+//! slow days like day 3 are only sampled once.
+
+use aoc19::{
+    intcode::{IntCode, Tracer},
+    registry::{load_input, solutions},
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    env, fs, io,
+    path::PathBuf,
+    process,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+const MAX_SAMPLES: usize = 100;
+
+type Bench = Box<dyn Fn() -> Duration>;
+
+/// Decrement a counter until it reaches zero, two instructions per loop
+fn countdown(n: i64) -> Vec<i64> {
+    vec![1001, 8, -1, 8, 1005, 8, 0, 99, n]
+}
+
+/// Read a value and write it back forever
+const ECHO: [i64; 7] = [3, 0, 4, 0, 1105, 1, 0];
+
+struct Options {
+    filter: Option<String>,
+    save: Option<String>,
+    baseline: Option<String>,
+    threshold: f64,
+    budget: Duration,
+}
+
+impl Options {
+    fn parse() -> Options {
+        let mut options = Options {
+            filter: None,
+            save: None,
+            baseline: None,
+            threshold: 10.0,
+            budget: Duration::from_millis(1000),
+        };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match &arg[..] {
+                // Passed by cargo bench
+                "--bench" => {}
+                "--save-baseline" => options.save = Some(value(args.next())),
+                "--baseline" => options.baseline = Some(value(args.next())),
+                "--threshold" => options.threshold = number(args.next()),
+                "--budget" => options.budget = Duration::from_millis(number(args.next())),
+                f if !f.starts_with('-') => options.filter = Some(f.to_string()),
+                _ => usage(),
+            }
+        }
+        options
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: cargo bench --bench solutions -- [filter] [--save-baseline <name>] [--baseline <name>] [--threshold <percent>] [--budget <ms>]"
+    );
+    process::exit(2);
+}
+
+fn value(arg: Option<String>) -> String {
+    arg.unwrap_or_else(|| usage())
+}
+
+fn number<T: std::str::FromStr>(arg: Option<String>) -> T {
+    value(arg).parse().unwrap_or_else(|_| usage())
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "target",
+        "bench",
+        &format!("{}.txt", name),
+    ]
+    .iter()
+    .collect()
+}
+
+/// A baseline is one `label: nanoseconds` line per benchmark
+fn load_baseline(name: &str) -> io::Result<BTreeMap<String, u128>> {
+    let content = fs::read_to_string(baseline_path(name))?;
+    Ok(content
+        .lines()
+        .filter_map(|l| {
+            let (label, nanos) = l.rsplit_once(':')?;
+            Some((label.to_string(), nanos.trim().parse().ok()?))
+        })
+        .collect())
+}
+
+fn save_baseline(name: &str, measures: &[(String, Duration)]) -> io::Result<()> {
+    let path = baseline_path(name);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content: String = measures
+        .iter()
+        .map(|(label, d)| format!("{}: {}\n", label, d.as_nanos()))
+        .collect();
+    fs::write(path, content)
+}
+
+/// Median of the samples taken by `f` until the budget is spent
+fn sample<F: FnMut() -> Duration>(budget: Duration, mut f: F) -> Duration {
+    let start = Instant::now();
+    let mut samples = vec![];
+    while samples.is_empty() || (start.elapsed() < budget && samples.len() < MAX_SAMPLES) {
+        samples.push(f());
+    }
+    samples.sort();
+    samples[samples.len() / 2]
+}
+
+fn timed<F: FnOnce()>(f: F) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn format_duration(d: Duration) -> String {
+    let nanos = d.as_nanos() as f64;
+    if nanos >= 1e9 {
+        format!("{:.2} s", nanos / 1e9)
+    } else if nanos >= 1e6 {
+        format!("{:.2} ms", nanos / 1e6)
+    } else if nanos >= 1e3 {
+        format!("{:.2} µs", nanos / 1e3)
+    } else {
+        format!("{} ns", nanos)
+    }
+}
+
+fn main() {
+    let options = Options::parse();
+    let selected = |label: &str| options.filter.as_ref().is_none_or(|f| label.contains(f));
+    let mut measures: Vec<(String, Duration)> = vec![];
+
+    for solution in solutions() {
+        let label = solution.label();
+        if !selected(&label) {
+            continue;
+        }
+        let input = match load_input(solution.day) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("{}: skipped, {}", label, e);
+                continue;
+            }
+        };
+        // The generator runs again with each part, only keep its first measure
+        let mut generators = vec![];
+        let runner = sample(options.budget, || match solution.run(&input) {
+            Ok(o) => {
+                generators.push(o.generator);
+                o.runner
+            }
+            Err(e) => {
+                eprintln!("{}: failed, {}", label, e);
+                process::exit(1);
+            }
+        });
+        if solution.part == 1 && solution.name.is_none() {
+            generators.sort();
+            let generator = generators[generators.len() / 2];
+            measures.push((format!("Day {} - Generator", solution.day), generator));
+        }
+        measures.push((label, runner));
+    }
+
+    let intcode: Vec<(&str, Bench)> = vec![
+        (
+            "IntCode - Countdown 1M",
+            Box::new(|| {
+                let mut i = IntCode::new(countdown(1_000_000), vec![]);
+                timed(|| {
+                    i.execute();
+                })
+            }),
+        ),
+        (
+            "IntCode - Echo 100k",
+            Box::new(|| {
+                let mut i = IntCode::new(ECHO.to_vec(), vec![]);
+                timed(|| {
+                    for n in 0..100_000 {
+                        i.push_input(n);
+                        i.execute();
+                    }
+                })
+            }),
+        ),
+        (
+            "IntCode - Traced countdown 100k",
+            Box::new(|| {
+                let mut i = IntCode::new(countdown(100_000), vec![]);
+                i.attach(Rc::new(RefCell::new(Tracer::new(io::sink()))));
+                timed(|| {
+                    i.execute();
+                })
+            }),
+        ),
+    ];
+    for (label, f) in intcode {
+        if selected(label) {
+            measures.push((label.to_string(), sample(options.budget, f)));
+        }
+    }
+
+    let baseline = options.baseline.as_ref().map(|name| {
+        load_baseline(name).unwrap_or_else(|e| {
+            eprintln!("Cannot load baseline {}: {}", name, e);
+            process::exit(2);
+        })
+    });
+    let mut regressions = 0;
+    for (label, measure) in &measures {
+        let before = baseline
+            .as_ref()
+            .and_then(|b| b.get(label))
+            .map(|&n| Duration::from_nanos(n as u64));
+        match before {
+            Some(before) => {
+                let change =
+                    (measure.as_nanos() as f64 / before.as_nanos().max(1) as f64 - 1.0) * 100.0;
+                let verdict = if change > options.threshold {
+                    regressions += 1;
+                    "REGRESSED"
+                } else if change < -options.threshold {
+                    "improved"
+                } else {
+                    ""
+                };
+                let line = format!(
+                    "{:<36} {:>10} {:>10} {:>+8.1}% {}",
+                    label,
+                    format_duration(before),
+                    format_duration(*measure),
+                    change,
+                    verdict
+                );
+                println!("{}", line.trim_end());
+            }
+            None => println!("{:<36} {:>10}", label, format_duration(*measure)),
+        }
+    }
+
+    if let Some(name) = &options.save {
+        if let Err(e) = save_baseline(name, &measures) {
+            eprintln!("Cannot save baseline {}: {}", name, e);
+            process::exit(2);
+        }
+        eprintln!("Baseline saved to {}", baseline_path(name).display());
+    }
+    if regressions > 0 {
+        eprintln!("{} regression(s) above {}%", regressions, options.threshold);
+        process::exit(1);
+    }
+}
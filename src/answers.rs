@@ -0,0 +1,141 @@
+//! Known good answers, one file per year in `answers/` for the default
+//! inputs and one file per named input in `answers/<year>/`.
+//!
+//! Each line uses the label printed by aoc-runner followed by the answer,
+//! e.g. `Day 1 - Part 2: 4825810`, blank lines and `#` comments are ignored.
+
+use std::{collections::BTreeMap, fmt, fs, io, path::PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Key {
+    pub day: u32,
+    pub part: u32,
+    pub name: Option<String>,
+}
+
+impl Key {
+    pub fn new(day: u32, part: u32, name: Option<&str>) -> Self {
+        Key {
+            day,
+            part,
+            name: name.map(String::from),
+        }
+    }
+
+    fn parse(s: &str) -> Option<Key> {
+        let mut fields = s.split(" - ");
+        let day = fields.next()?.strip_prefix("Day ")?.parse().ok()?;
+        let part = fields.next()?.strip_prefix("Part ")?.parse().ok()?;
+        let name = fields.next().map(String::from);
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(Key { day, part, name })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Day {} - Part {}", self.day, self.part)?;
+        if let Some(n) = &self.name {
+            write!(f, " - {}", n)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Answers(BTreeMap<Key, String>);
+
+impl Answers {
+    /// `answers/2019.txt` for the default inputs, `answers/2019/alice.txt`
+    /// for the inputs named `alice`
+    pub fn path(year: u32, input: Option<&str>) -> PathBuf {
+        let dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "answers"].iter().collect();
+        match input {
+            Some(name) => dir.join(year.to_string()).join(format!("{}.txt", name)),
+            None => dir.join(format!("{}.txt", year)),
+        }
+    }
+
+    /// Answers of the year, empty if the file does not exist yet
+    pub fn load(year: u32, input: Option<&str>) -> io::Result<Answers> {
+        match fs::read_to_string(Answers::path(year, input)) {
+            Ok(s) => Answers::parse(&s),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, year: u32, input: Option<&str>) -> io::Result<()> {
+        let path = Answers::path(year, input);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(s: &str) -> io::Result<Answers> {
+        let mut answers = Answers::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line
+                .split_once(':')
+                .and_then(|(k, v)| Some((Key::parse(k.trim())?, v.trim())));
+            match parsed {
+                Some((key, value)) => answers.insert(key, value),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid answer line {}: {:?}", i + 1, line),
+                    ))
+                }
+            }
+        }
+        Ok(answers)
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, key: Key, value: S) {
+        self.0.insert(key, value.into());
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (k, v) in &self.0 {
+            writeln!(f, "{}: {}", k, v)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let answers = Answers::parse(
+            "# 2019\nDay 1 - Part 1: 3219099\n\nDay 2 - Part 2 - BruteForce: 3951\n",
+        )
+        .unwrap();
+        assert_eq!(answers.get(&Key::new(1, 1, None)), Some("3219099"));
+        assert_eq!(
+            answers.get(&Key::new(2, 2, Some("BruteForce"))),
+            Some("3951")
+        );
+        assert_eq!(answers.get(&Key::new(2, 2, None)), None);
+        assert_eq!(
+            answers.to_string(),
+            "Day 1 - Part 1: 3219099\nDay 2 - Part 2 - BruteForce: 3951\n"
+        );
+        assert!(Answers::parse("Day one - Part 1: 3").is_err());
+    }
+}
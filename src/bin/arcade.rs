@@ -0,0 +1,283 @@
+//! Play the day 13 arcade game in the terminal.
+//!
+//! ```text
+//! arcade [--watch] [--strategy <name>] [--replay <path>] [--save <path>] [--fps <n>]
+//! arcade --report [--replay <path>]
+//!     -w, --watch            let the paddle follow the ball instead of reading the keys
+//!     -s, --strategy <name>  watch the `follow` or `intercept` strategy play
+//!     -r, --replay <path>    watch the joystick moves saved by `--save`
+//!     --save <path>          save the joystick moves of the game
+//!     -f, --fps <n>          frames per second, 10 by default
+//!     --report               play with every strategy without drawing, printing
+//!                            the frames and instructions each one needs
+//! ```
+//!
+//! The left and right arrow keys tilt the joystick, `q` quits.
+
+use aoc19::{
+    day13::{
+        input_generator, play_with, Arcade, Follow, Intercept, Joystick, Replay, Report, Status,
+    },
+    registry::load_input,
+};
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    process::{self, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: arcade [--watch] [--strategy <name>] [--replay <path>] [--save <path>] [--fps <n>]"
+    );
+    eprintln!("       arcade --report [--replay <path>]");
+    process::exit(2);
+}
+
+#[derive(Default)]
+struct Options {
+    strategy: Option<String>,
+    replay: Option<String>,
+    save: Option<String>,
+    report: bool,
+}
+
+/// Comma separated joystick positions
+fn load_moves(path: &str) -> Result<Vec<i64>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let moves = text
+        .trim()
+        .split(',')
+        .filter(|m| !m.is_empty())
+        .map(|m| m.trim().parse())
+        .collect::<Result<_, _>>()?;
+    Ok(moves)
+}
+
+fn save_moves(path: &str, moves: &[i64]) -> io::Result<()> {
+    let moves: Vec<String> = moves.iter().map(i64::to_string).collect();
+    fs::write(path, moves.join(",") + "\n")
+}
+
+fn strategy(options: &Options) -> Result<Option<Box<dyn Joystick>>, Box<dyn Error>> {
+    if let Some(path) = &options.replay {
+        return Ok(Some(Box::new(Replay::new(load_moves(path)?))));
+    }
+    Ok(match options.strategy.as_deref() {
+        None => None,
+        Some("follow") => Some(Box::new(Follow)),
+        Some("intercept") => Some(Box::new(Intercept)),
+        Some(name) => return Err(format!("unknown strategy {:?}", name).into()),
+    })
+}
+
+fn report(program: &[i64], options: &Options) -> Result<(), Box<dyn Error>> {
+    let follow = play_with(program, &mut Follow)?;
+    let intercept = play_with(program, &mut Intercept)?;
+    let (replay_name, moves) = match &options.replay {
+        Some(path) => (format!("replay {}", path), load_moves(path)?),
+        None => ("replay follow".to_string(), follow.moves.clone()),
+    };
+    let replay = play_with(program, &mut Replay::new(moves))?;
+    let reports: [(&str, &Report); 3] = [
+        ("follow", &follow),
+        ("intercept", &intercept),
+        (&replay_name, &replay),
+    ];
+    let width = reports.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    println!(
+        "{:<width$}  {:>4}  {:>6}  {:>6}  {:>12}",
+        "Strategy",
+        "Won",
+        "Score",
+        "Frames",
+        "Instructions",
+        width = width
+    );
+    for (name, r) in reports.iter() {
+        println!(
+            "{:<width$}  {:>4}  {:>6}  {:>6}  {:>12}",
+            name,
+            if r.won { "yes" } else { "no" },
+            r.score,
+            r.frames,
+            r.instructions,
+            width = width
+        );
+    }
+    Ok(())
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed, is stdin a terminal?"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Keys are read without echo nor waiting for a new line, the settings are
+/// restored when dropped
+struct RawTerminal {
+    saved: String,
+}
+
+impl RawTerminal {
+    /// Reading stdin waits for a key at most `tick`
+    fn new(tick: Duration) -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        let tenths = (tick.as_millis() / 100).clamp(1, 255).to_string();
+        stty(&["raw", "-echo", "min", "0", "time", &tenths])?;
+        Ok(RawTerminal { saved })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+enum Key {
+    Left,
+    Right,
+    Quit,
+}
+
+/// The last key among the bytes read, arrows being `ESC [ D` and `ESC [ C`
+fn last_key(bytes: &[u8]) -> Option<Key> {
+    let mut key = None;
+    for (i, &b) in bytes.iter().enumerate() {
+        match (b, bytes.get(i.wrapping_sub(2)..i)) {
+            (b'D', Some(b"\x1b[")) => key = Some(Key::Left),
+            (b'C', Some(b"\x1b[")) => key = Some(Key::Right),
+            // Ctrl-C is not a signal in raw mode
+            (b'q', _) | (3, _) => return Some(Key::Quit),
+            _ => {}
+        }
+    }
+    key
+}
+
+fn draw(arcade: &Arcade, newline: &str) -> io::Result<()> {
+    let mut frame = String::from("\x1b[H");
+    for row in arcade.render() {
+        frame.push_str(&row);
+        frame.push_str(newline);
+    }
+    frame.push_str(&format!(
+        "Score: {}  Blocks: {}\x1b[K{}",
+        arcade.score(),
+        arcade.blocks(),
+        newline
+    ));
+    let mut out = io::stdout();
+    out.write_all(frame.as_bytes())?;
+    out.flush()
+}
+
+/// Joystick moves of the game, read from the keys without a strategy
+fn play(
+    arcade: &mut Arcade,
+    mut strategy: Option<Box<dyn Joystick>>,
+    tick: Duration,
+) -> Result<Vec<i64>, Box<dyn Error>> {
+    let watch = strategy.is_some();
+    let terminal = if watch {
+        None
+    } else {
+        Some(RawTerminal::new(tick)?)
+    };
+    let newline = if watch { "\n" } else { "\r\n" };
+    print!("\x1b[2J\x1b[?25l");
+    let mut stdin = io::stdin();
+    let mut buffer = [0; 64];
+    let mut moves = vec![];
+    while arcade.run()? == Status::Joystick {
+        let start = Instant::now();
+        draw(arcade, newline)?;
+        let position = match &mut strategy {
+            Some(strategy) => strategy.position(arcade),
+            None => {
+                let read = stdin.read(&mut buffer)?;
+                match last_key(&buffer[..read]) {
+                    Some(Key::Left) => -1,
+                    Some(Key::Right) => 1,
+                    Some(Key::Quit) => break,
+                    None => 0,
+                }
+            }
+        };
+        moves.push(position);
+        arcade.joystick(position);
+        thread::sleep(tick.saturating_sub(start.elapsed()));
+    }
+    draw(arcade, newline)?;
+    drop(terminal);
+    print!("\x1b[?25h");
+    Ok(moves)
+}
+
+fn main() {
+    let mut options = Options::default();
+    let mut fps = 10;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-w" | "--watch" => options.strategy = Some("follow".to_string()),
+            "-s" | "--strategy" => options.strategy = Some(args.next().unwrap_or_else(|| usage())),
+            "-r" | "--replay" => options.replay = Some(args.next().unwrap_or_else(|| usage())),
+            "--save" => options.save = Some(args.next().unwrap_or_else(|| usage())),
+            "--report" => options.report = true,
+            "-f" | "--fps" => {
+                fps = args
+                    .next()
+                    .and_then(|f| f.parse().ok())
+                    .filter(|&f| f > 0)
+                    .unwrap_or_else(|| usage())
+            }
+            _ => usage(),
+        }
+    }
+    let program = load_input(13)
+        .map_err(|e| e.to_string())
+        .and_then(|input| input_generator(&input).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("Cannot load the arcade program: {}", e);
+            process::exit(2);
+        });
+    if options.report {
+        if let Err(e) = report(&program, &options) {
+            eprintln!("Arcade failure: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    let mut arcade = Arcade::new(&program, true).unwrap_or_else(|e| {
+        eprintln!("Cannot load the arcade program: {}", e);
+        process::exit(2);
+    });
+    let tick = Duration::from_secs(1) / fps;
+    let played = strategy(&options).and_then(|strategy| play(&mut arcade, strategy, tick));
+    let saved = played.and_then(|moves| match &options.save {
+        Some(path) => Ok(save_moves(path, &moves)?),
+        None => Ok(()),
+    });
+    if let Err(e) = saved {
+        print!("\x1b[?25h");
+        eprintln!("Arcade failure: {}", e);
+        process::exit(1);
+    }
+    if arcade.blocks() == 0 {
+        println!("All the blocks are broken, final score {}", arcade.score());
+    } else {
+        println!("Game over, score {}", arcade.score());
+    }
+}
@@ -0,0 +1,266 @@
+//! Run an arbitrary IntCode program.
+//!
+//! ```text
+//! intcode <program> [options]
+//!     -i, --input <values>     inputs given on the command line
+//!     -f, --input-file <path>  inputs read from a file
+//!     -s, --stdin              read a line of stdin whenever an input is missing
+//!     -a, --ascii              inputs and outputs are ASCII text
+//!     -t, --trace              print every executed instruction on stderr
+//!     -d, --debug              open a debugger when the program does not stop cleanly,
+//!                              e.g. on an invalid address or an overflow
+//!     --heatmap <path>         save the memory activity as a PNG, PPM or PGM image,
+//!                              or as an animation of its frames for a GIF
+//!     --heatmap-animate        replay the memory activity in the terminal
+//! ```
+
+use aoc19::{
+    heatmap::Heatmap,
+    image,
+    intcode::{load_program, Counter, ExitCode, IntCode, Observer, Tracer, MAX_MEMORY},
+};
+use std::{
+    cell::RefCell,
+    env, fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    process,
+    rc::Rc,
+    time::Duration,
+};
+
+#[derive(Default)]
+struct Options {
+    program: String,
+    inputs: Vec<String>,
+    input_files: Vec<String>,
+    stdin: bool,
+    ascii: bool,
+    trace: bool,
+    debug: bool,
+    heatmap: Option<String>,
+    heatmap_animate: bool,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut program = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("Missing value for {}", name));
+        match &arg[..] {
+            "-i" | "--input" => options.inputs.push(value(&arg)?),
+            "-f" | "--input-file" => options.input_files.push(value(&arg)?),
+            "-s" | "--stdin" => options.stdin = true,
+            "-a" | "--ascii" => options.ascii = true,
+            "-t" | "--trace" => options.trace = true,
+            "-d" | "--debug" => options.debug = true,
+            "--heatmap" => options.heatmap = Some(value(&arg)?),
+            "--heatmap-animate" => options.heatmap_animate = true,
+            a if a.starts_with('-') => return Err(format!("Unknown option {}", a)),
+            _ if program.is_none() => program = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+    options.program = program.ok_or("Missing program path")?;
+    Ok(options)
+}
+
+fn parse_values(s: &str, ascii: bool) -> Result<Vec<i64>, String> {
+    if ascii {
+        let mut values: Vec<i64> = s.chars().map(|c| c as i64).collect();
+        if !s.ends_with('\n') {
+            values.push('\n' as i64);
+        }
+        return Ok(values);
+    }
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse()
+                .map_err(|_| format!("Invalid input value {:?}", v))
+        })
+        .collect()
+}
+
+fn print_output(val: i64, ascii: bool) {
+    match val {
+        0..=127 if ascii => print!("{}", val as u8 as char),
+        _ => println!("{}", val),
+    }
+}
+
+fn read_line() -> Option<String> {
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line),
+    }
+}
+
+fn run(machine: &mut IntCode, options: &Options) -> ExitCode {
+    loop {
+        match machine.execute() {
+            ExitCode::Output(v) => print_output(v, options.ascii),
+            ExitCode::AwaitInput if options.stdin => {
+                let _ = io::stdout().flush();
+                match read_line().map(|l| parse_values(&l, options.ascii)) {
+                    Some(Ok(values)) => values.into_iter().for_each(|v| machine.push_input(v)),
+                    Some(Err(e)) => eprintln!("{}", e),
+                    None => return ExitCode::AwaitInput,
+                }
+            }
+            code => return code,
+        }
+    }
+}
+
+const HELP: &str = "Commands:
+    r, regs                 show the machine registers
+    m, mem <addr> [len]     dump memory
+    w, write <addr> <value> write to memory
+    j, jump <addr>          move the instruction pointer
+    i, input <values>       push inputs
+    s, step                 execute one instruction
+    c, continue             run until the program pauses
+    q, quit                 leave the debugger";
+
+fn debugger(machine: &mut IntCode, options: &Options) {
+    println!("Entering debugger, type h for help");
+    loop {
+        print!("(intcode) ");
+        let _ = io::stdout().flush();
+        let line = match read_line() {
+            Some(l) => l,
+            None => return,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let num = |i: usize| words.get(i).and_then(|w| w.parse::<i64>().ok());
+        match (words.first().cloned().unwrap_or(""), num(1), num(2)) {
+            ("r", ..) | ("regs", ..) => {
+                let value = machine.content.get(machine.index).cloned();
+                println!(
+                    "index {} ({:?}), relative base {}, inputs {}/{}, outputs {}",
+                    machine.index,
+                    value,
+                    machine.relative_base,
+                    machine.manual_input_index,
+                    machine.manual_input.len(),
+                    machine.output.len()
+                );
+            }
+            ("m", Some(addr), len) | ("mem", Some(addr), len) => {
+                let start = (addr.max(0) as usize).min(machine.content.len());
+                let end = start
+                    .saturating_add(len.unwrap_or(8).max(0) as usize)
+                    .min(machine.content.len());
+                println!("{}: {:?}", start, &machine.content[start..end]);
+            }
+            ("w", Some(addr), Some(val)) | ("write", Some(addr), Some(val)) => {
+                if (0..MAX_MEMORY as i64).contains(&addr) {
+                    machine.write_result_to_addr(addr as usize, val)
+                } else {
+                    println!("Address {} is out of the memory", addr)
+                }
+            }
+            ("j", Some(addr), _) | ("jump", Some(addr), _) => {
+                if (0..machine.content.len() as i64).contains(&addr) {
+                    machine.index = addr as usize
+                } else {
+                    println!("Address {} is out of the program", addr)
+                }
+            }
+            ("i", ..) | ("input", ..) => {
+                let rest = line.trim_start()[words[0].len()..].trim_start();
+                match parse_values(rest, options.ascii) {
+                    Ok(values) => values.into_iter().for_each(|v| machine.push_input(v)),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            ("s", ..) | ("step", ..) => {
+                let mut tracer = Tracer::new(io::stdout());
+                tracer.before_instruction(machine);
+                match machine.step() {
+                    Some(ExitCode::Output(v)) => print_output(v, options.ascii),
+                    Some(code) => println!("{:?}", code),
+                    None => {}
+                }
+            }
+            ("c", ..) | ("continue", ..) => println!("{:?}", run(machine, options)),
+            ("q", ..) | ("quit", ..) => return,
+            ("", ..) => {}
+            _ => println!("{}", HELP),
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}\n\nUsage: intcode <program> [-i values] [-f path] [-s] [-a] [-t] [-d] [--heatmap path] [--heatmap-animate]", e);
+            process::exit(2);
+        }
+    };
+    let program = match load_program(&options.program) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Cannot load {}: {}", options.program, e);
+            process::exit(2);
+        }
+    };
+
+    let mut machine = IntCode::new(program, vec![]);
+    let sources = options.input_files.iter().map(fs::read_to_string);
+    for source in options.inputs.iter().cloned().map(Ok).chain(sources) {
+        match source
+            .map_err(|e| e.to_string())
+            .and_then(|s| parse_values(&s, options.ascii))
+        {
+            Ok(values) => values.into_iter().for_each(|v| machine.push_input(v)),
+            Err(e) => {
+                eprintln!("Cannot read inputs: {}", e);
+                process::exit(2);
+            }
+        }
+    }
+
+    let counter = Rc::new(RefCell::new(Counter::default()));
+    machine.attach(counter.clone());
+    if options.trace {
+        machine.attach(Rc::new(RefCell::new(Tracer::new(io::stderr()))));
+    }
+    let heatmap = Rc::new(RefCell::new(Heatmap::new(1000)));
+    if options.heatmap.is_some() || options.heatmap_animate {
+        machine.attach(heatmap.clone());
+    }
+
+    let code = run(&mut machine, &options);
+    let _ = io::stdout().flush();
+    eprintln!("{:?} after {} instructions", code, counter.borrow().0);
+
+    if options.heatmap_animate {
+        let heatmap = heatmap.borrow();
+        if let Err(e) = heatmap.animate(&mut io::stdout(), 64, Duration::from_millis(100)) {
+            eprintln!("Cannot animate heatmap: {}", e);
+        }
+    }
+    if let Some(path) = &options.heatmap {
+        let heatmap = heatmap.borrow();
+        let gif = Path::new(path)
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("gif"));
+        let saved = if gif {
+            heatmap.animation(64, 10, 4).save(path)
+        } else {
+            let image = heatmap.total().image(heatmap.len(), 64);
+            image::save(path, &image::rasterize(&image, 4, |&c| c))
+        };
+        if let Err(e) = saved {
+            eprintln!("Cannot save heatmap to {}: {}", path, e);
+        }
+    }
+    if code != ExitCode::Stop && options.debug {
+        debugger(&mut machine, &options);
+    }
+    process::exit(if code == ExitCode::Stop { 0 } else { 1 });
+}
@@ -0,0 +1,138 @@
+//! Record the simulations of the puzzles as animated GIFs.
+//!
+//! ```text
+//! record <day> <path> [--fps <n>] [--scale <pixels>] [--every <n>] [--steps <n>]
+//!     <day>                 10 for the laser sweep, 11 for the hull painting
+//!                           robot, 12 for the moons, 13 for the arcade game
+//!     <path>                .gif file to write
+//!     -f, --fps <n>         frames per second, 25 by default
+//!     -s, --scale <pixels>  size of the square drawn for each cell, 4 by default
+//!     -e, --every <n>       only keep one frame out of n, 1 by default
+//!     -n, --steps <n>       steps of the moons simulation, 1000 by default
+//! ```
+
+use aoc19::{day10, day11, day12, day13, gif::Animation, image::Rgb, registry::load_input};
+use std::{env, error::Error, process};
+
+const BACKGROUND: Rgb = [0x0f, 0x0f, 0x23];
+
+/// Empty, asteroid, station and the asteroid being vaporized
+const SKY_PALETTE: [Rgb; 4] = [
+    BACKGROUND,
+    [0xa0, 0xa0, 0xa0],
+    [0x33, 0xff, 0x66],
+    [0xff, 0x33, 0x33],
+];
+
+/// Black panel, white panel and robot
+const HULL_PALETTE: [Rgb; 3] = [BACKGROUND, [0xff, 0xff, 0xff], [0xff, 0x33, 0x33]];
+
+/// Empty space then one colour per moon
+const MOONS_PALETTE: [Rgb; 5] = [
+    BACKGROUND,
+    [0xff, 0x66, 0x33],
+    [0x33, 0x99, 0xff],
+    [0x33, 0xff, 0x66],
+    [0xff, 0xff, 0x66],
+];
+
+/// Empty, wall, block, paddle and ball
+const SCREEN_PALETTE: [Rgb; 5] = [
+    BACKGROUND,
+    [0x80, 0x80, 0x80],
+    [0x33, 0x99, 0xff],
+    [0xff, 0xff, 0xff],
+    [0xff, 0x33, 0x33],
+];
+
+struct Options {
+    fps: u32,
+    scale: usize,
+    every: usize,
+    steps: usize,
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: record <10|11|12|13> <path> [--fps <n>] [--scale <pixels>] [--every <n>] [--steps <n>]");
+    process::exit(2);
+}
+
+fn record(day: u32, options: &Options) -> Result<Animation, Box<dyn Error>> {
+    let input = load_input(day)?;
+    let new = |palette: &[Rgb]| Animation::new(palette.to_vec(), options.fps, options.scale);
+    Ok(match day {
+        10 => {
+            let mut animation = new(&SKY_PALETTE);
+            let asteroids = day10::input_generator(&input)?;
+            for frame in day10::record(&asteroids, options.every)? {
+                animation.push(&frame, |&c| c as u8);
+            }
+            animation
+        }
+        11 => {
+            let mut animation = new(&HULL_PALETTE);
+            let program = day11::input_generator(&input)?;
+            for frame in day11::record(&program, 1, options.every)? {
+                animation.push(&frame, |&c| c as u8);
+            }
+            animation
+        }
+        12 => {
+            let mut animation = new(&MOONS_PALETTE);
+            let moons = day12::input_generator(&input)?;
+            let frames = day12::record(&moons, options.steps, 64);
+            for frame in frames.iter().step_by(options.every) {
+                animation.push(frame, |&m| m.min(MOONS_PALETTE.len() - 1) as u8);
+            }
+            animation
+        }
+        13 => {
+            let mut animation = new(&SCREEN_PALETTE);
+            let program = day13::input_generator(&input)?;
+            for frame in day13::record(&program, options.every)? {
+                animation.push(&frame, |&t| t as u8);
+            }
+            animation
+        }
+        _ => usage(),
+    })
+}
+
+fn main() {
+    let mut positional = vec![];
+    let mut options = Options {
+        fps: 25,
+        scale: 4,
+        every: 1,
+        steps: 1000,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .and_then(|s| s.parse::<usize>().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or_else(|| usage())
+        };
+        match &arg[..] {
+            "-f" | "--fps" => options.fps = value() as u32,
+            "-s" | "--scale" => options.scale = value(),
+            "-e" | "--every" => options.every = value(),
+            "-n" | "--steps" => options.steps = value(),
+            _ => positional.push(arg),
+        }
+    }
+    let (day, path) = match &positional[..] {
+        [day, path] => (day.parse().unwrap_or_else(|_| usage()), path),
+        _ => usage(),
+    };
+    let saved = record(day, &options).and_then(|animation| {
+        animation.save(path)?;
+        println!("{} frames recorded in {}", animation.len(), path);
+        Ok(())
+    });
+    if let Err(e) = saved {
+        eprintln!("Cannot record day {}: {}", day, e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,124 @@
+//! Save the pictures drawn by the puzzles as image files.
+//!
+//! ```text
+//! render <day> <path> [--scale <pixels>] [--palette <colours>]
+//!     <day>                    8 for the decoded image, 11 for the hull
+//!                              registration, 13 for the arcade screen, 15
+//!                              for the map explored by the repair droid
+//!     <path>                   .png, .ppm or .pgm file to write
+//!     -s, --scale <pixels>     size of the square drawn for each cell, 8 by default
+//!     -p, --palette <colours>  comma separated `rrggbb` colours, indexed by
+//!                              the cell value, dark pixels then lit ones for
+//!                              the images, the tile ids for the screen and
+//!                              unknown, wall, open, oxygen and start for the map
+//! ```
+
+use aoc19::{
+    day11, day13,
+    day15::{self, Tile},
+    day8,
+    image::{self, parse_colour, Rgb},
+    registry::load_input,
+};
+use std::{env, error::Error, process};
+
+const IMAGE_PALETTE: [Rgb; 2] = [[0x0f, 0x0f, 0x23], [0xff, 0xff, 0x66]];
+
+/// Empty, wall, block, paddle and ball
+const SCREEN_PALETTE: [Rgb; 5] = [
+    [0x0f, 0x0f, 0x23],
+    [0x80, 0x80, 0x80],
+    [0x33, 0x99, 0xff],
+    [0xff, 0xff, 0xff],
+    [0xff, 0x33, 0x33],
+];
+
+/// Unknown, wall, open, oxygen system and start of the droid
+const MAP_PALETTE: [Rgb; 5] = [
+    [0x0f, 0x0f, 0x23],
+    [0x80, 0x80, 0x80],
+    [0xe0, 0xe0, 0xe0],
+    [0x33, 0x99, 0xff],
+    [0xff, 0x33, 0x33],
+];
+
+fn usage() -> ! {
+    eprintln!("Usage: render <8|11|13|15> <path> [--scale <pixels>] [--palette <colours>]");
+    process::exit(2);
+}
+
+fn colour(palette: &[Rgb], index: i64) -> Rgb {
+    *palette.get(index as usize).unwrap_or(&image::BLACK)
+}
+
+fn render(
+    day: u32,
+    scale: usize,
+    palette: Option<Vec<Rgb>>,
+) -> Result<image::Image, Box<dyn Error>> {
+    let input = load_input(day)?;
+    Ok(match day {
+        8 => {
+            let picture = day8::input_generator(&input)?.decode();
+            let palette = palette.unwrap_or_else(|| IMAGE_PALETTE.to_vec());
+            image::rasterize(&picture, scale, |&p| colour(&palette, p as i64))
+        }
+        11 => {
+            let picture = day11::hull(&day11::input_generator(&input)?)?;
+            let palette = palette.unwrap_or_else(|| IMAGE_PALETTE.to_vec());
+            image::rasterize(&picture, scale, |&p| colour(&palette, p as i64))
+        }
+        13 => {
+            let screen = day13::screen(&day13::input_generator(&input)?)?;
+            let palette = palette.unwrap_or_else(|| SCREEN_PALETTE.to_vec());
+            image::rasterize(&screen.to_dense(0), scale, |&t| colour(&palette, t))
+        }
+        15 => {
+            let map = day15::explore(&day15::input_generator(&input)?)?;
+            let mut cells = map.map(|tile| match tile {
+                Tile::Wall => 1,
+                Tile::Open => 2,
+                Tile::Oxygen => 3,
+            });
+            cells.set(0, 0, 4);
+            let palette = palette.unwrap_or_else(|| MAP_PALETTE.to_vec());
+            image::rasterize(&cells.to_dense(0), scale, |&c| colour(&palette, c))
+        }
+        _ => usage(),
+    })
+}
+
+fn main() {
+    let mut positional = vec![];
+    let mut scale = 8;
+    let mut palette = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-s" | "--scale" => {
+                scale = args
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&s| s > 0)
+                    .unwrap_or_else(|| usage())
+            }
+            "-p" | "--palette" => {
+                let colours: Option<Vec<Rgb>> = args
+                    .next()
+                    .map(|p| p.split(',').map(parse_colour).collect())
+                    .unwrap_or(None);
+                palette = Some(colours.unwrap_or_else(|| usage()));
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let (day, path) = match &positional[..] {
+        [day, path] => (day.parse().unwrap_or_else(|_| usage()), path),
+        _ => usage(),
+    };
+    let saved = render(day, scale, palette).and_then(|image| Ok(image::save(path, &image)?));
+    if let Err(e) = saved {
+        eprintln!("Cannot render day {}: {}", day, e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,127 @@
+//! Run solutions and print their answers and timings as JSON.
+//!
+//! ```text
+//! report [--day <day>]... [--part <part>]... [--output <path>]
+//!     -d, --day <day>      only run this day, can be repeated
+//!     -p, --part <part>    only run this part, can be repeated
+//!     -o, --output <path>  write the JSON to a file instead of stdout
+//! ```
+//!
+//! Every solution runs on each of its inputs, `"input"` is `null` for the
+//! default `dayN.txt` one and the file name for the `dayN/<name>.txt` ones.
+//! A day without any input gets a single result with a `"no input"` error.
+
+use aoc19::{
+    registry::{inputs, solutions},
+    YEAR,
+};
+use std::{env, fs, process, time::Duration};
+
+fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+fn nanos(d: Duration) -> String {
+    d.as_nanos().to_string()
+}
+
+fn main() {
+    let mut days = vec![];
+    let mut parts = vec![];
+    let mut output = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match (&arg[..], value.as_ref().and_then(|v| v.parse::<u32>().ok())) {
+            ("-d", Some(d)) | ("--day", Some(d)) => days.push(d),
+            ("-p", Some(p)) | ("--part", Some(p)) => parts.push(p),
+            ("-o", _) | ("--output", _) if value.is_some() => output = value,
+            _ => {
+                eprintln!("Usage: report [--day <day>]... [--part <part>]... [--output <path>]");
+                process::exit(2);
+            }
+        }
+    }
+
+    let mut results = vec![];
+    for solution in solutions() {
+        if !days.is_empty() && !days.contains(&solution.day) {
+            continue;
+        }
+        if !parts.is_empty() && !parts.contains(&solution.part) {
+            continue;
+        }
+        let name = solution.name.map_or("null".to_string(), escape);
+        let inputs = inputs(solution.day).unwrap_or_else(|e| {
+            eprintln!("Cannot list the inputs of day {}: {}", solution.day, e);
+            process::exit(2);
+        });
+        if inputs.is_empty() {
+            results.push(format!(
+                "    {{\"day\": {}, \"part\": {}, \"name\": {}, \"input\": null, \"answer\": null, \"generator_ns\": null, \"runner_ns\": null, \"error\": {}}}",
+                solution.day,
+                solution.part,
+                name,
+                escape("no input")
+            ));
+        }
+        for input in inputs {
+            let outcome = input
+                .load()
+                .map_err(|e| e.into())
+                .and_then(|input| solution.run(&input));
+            let (answer, generator, runner, error) = match outcome {
+                Ok(o) => (
+                    escape(&o.answer.line()),
+                    nanos(o.generator),
+                    nanos(o.runner),
+                    "null".into(),
+                ),
+                Err(e) => (
+                    "null".into(),
+                    "null".into(),
+                    "null".into(),
+                    escape(&e.to_string()),
+                ),
+            };
+            results.push(format!(
+                "    {{\"day\": {}, \"part\": {}, \"name\": {}, \"input\": {}, \"answer\": {}, \"generator_ns\": {}, \"runner_ns\": {}, \"error\": {}}}",
+                solution.day,
+                solution.part,
+                name,
+                input.name.as_deref().map_or("null".to_string(), escape),
+                answer,
+                generator,
+                runner,
+                error
+            ));
+        }
+    }
+    let json = format!(
+        "{{\n  \"year\": {},\n  \"results\": [\n{}\n  ]\n}}\n",
+        YEAR,
+        results.join(",\n")
+    );
+
+    match output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, json) {
+                eprintln!("Cannot write {}: {}", path, e);
+                process::exit(2);
+            }
+        }
+        None => print!("{}", json),
+    }
+}
@@ -0,0 +1,119 @@
+//! Run every solution on its inputs and compare with the stored answers.
+//!
+//! ```text
+//! verify [--day <day>]... [--input <name>]... [--record]
+//!     -d, --day <day>     only verify this day, can be repeated
+//!     -i, --input <name>  only verify this named input, `default` for the
+//!                         `dayN.txt` ones, can be repeated
+//!     -r, --record        store the answers of the solutions without one
+//! ```
+//!
+//! Named inputs are read from `input/2019/dayN/<name>.txt` and checked against
+//! `answers/2019/<name>.txt`.
+//!
+//! Exits with a non-zero status if an answer differs or a solution fails.
+
+use aoc19::{
+    answers::{Answers, Key},
+    registry::{inputs, solutions},
+    YEAR,
+};
+use std::{collections::BTreeMap, env, process};
+
+fn usage() -> ! {
+    eprintln!("Usage: verify [--day <day>]... [--input <name>]... [--record]");
+    process::exit(2);
+}
+
+fn main() {
+    let mut days = vec![];
+    let mut names = vec![];
+    let mut record = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-d" | "--day" => match args.next().and_then(|d| d.parse::<u32>().ok()) {
+                Some(d) => days.push(d),
+                None => {
+                    eprintln!("Missing day number");
+                    process::exit(2);
+                }
+            },
+            "-i" | "--input" => match args.next() {
+                Some(name) => names.push(name),
+                None => usage(),
+            },
+            "-r" | "--record" => record = true,
+            _ => usage(),
+        }
+    }
+
+    let mut answers: BTreeMap<Option<String>, Answers> = BTreeMap::new();
+    let mut recorded: BTreeMap<Option<String>, usize> = BTreeMap::new();
+    let mut failures = 0;
+    for solution in solutions() {
+        if !days.is_empty() && !days.contains(&solution.day) {
+            continue;
+        }
+        let inputs = inputs(solution.day).unwrap_or_else(|e| {
+            eprintln!("Cannot list the inputs of day {}: {}", solution.day, e);
+            process::exit(2);
+        });
+        if inputs.is_empty() {
+            failures += 1;
+            println!("{}: FAILED, no input", solution.label());
+        }
+        for input in inputs {
+            let name = input.name.as_deref().unwrap_or("default");
+            if !names.is_empty() && !names.iter().any(|n| n == name) {
+                continue;
+            }
+            let stored = answers.entry(input.name.clone()).or_insert_with(|| {
+                Answers::load(YEAR, input.name.as_deref()).unwrap_or_else(|e| {
+                    eprintln!("Cannot load the answers of {}: {}", name, e);
+                    process::exit(2);
+                })
+            });
+            let key = Key::new(solution.day, solution.part, solution.name);
+            let label = match &input.name {
+                Some(name) => format!("{} [{}]", key, name),
+                None => key.to_string(),
+            };
+            let outcome = input
+                .load()
+                .map_err(|e| e.into())
+                .and_then(|input| solution.run(&input));
+            match (outcome.map(|o| (o.answer.line(), o)), stored.get(&key)) {
+                (Ok((answer, o)), Some(expected)) if answer == expected => {
+                    println!("{}: ok ({:?})", label, o.generator + o.runner)
+                }
+                (Ok((answer, _)), Some(expected)) => {
+                    failures += 1;
+                    println!("{}: MISMATCH, expected {} got {}", label, expected, answer)
+                }
+                (Ok((answer, _)), None) if record => {
+                    println!("{}: recorded {}", label, answer);
+                    stored.insert(key, answer);
+                    *recorded.entry(input.name).or_default() += 1;
+                }
+                (Ok((answer, _)), None) => {
+                    println!("{}: no stored answer, got {}", label, answer)
+                }
+                (Err(e), _) => {
+                    failures += 1;
+                    println!("{}: FAILED, {}", label, e)
+                }
+            }
+        }
+    }
+    for name in recorded.keys() {
+        if let Err(e) = answers[name].save(YEAR, name.as_deref()) {
+            eprintln!("Cannot save answers: {}", e);
+            process::exit(2);
+        }
+    }
+    if failures > 0 {
+        eprintln!("{} failure(s)", failures);
+        process::exit(1);
+    }
+}
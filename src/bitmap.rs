@@ -0,0 +1,58 @@
+//! Black and white images, as drawn by the puzzles printing their answer.
+
+use super::grid::DenseGrid;
+use std::fmt;
+
+/// Lit pixels are `true`
+pub type Bitmap = DenseGrid<bool>;
+
+impl DenseGrid<bool> {
+    /// Rows of the same length, `#` for lit pixels and anything else for dark
+    /// ones
+    pub fn from_rows<S: AsRef<str>>(rows: &[S]) -> Option<Self> {
+        let text: Vec<&str> = rows.iter().map(AsRef::as_ref).collect();
+        DenseGrid::parse(&text.join("\n"), |c| Some(c == '#')).ok()
+    }
+
+    /// Dark outside of the image
+    pub fn lit(&self, x: usize, y: usize) -> bool {
+        self.get(x, y) == Some(&true)
+    }
+
+    /// One line form used to store the image, rows of `#` and `.` separated
+    /// by `/`
+    pub fn encode(&self) -> String {
+        self.render(|&p| if p { '#' } else { '.' }).join("/")
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        Bitmap::from_rows(&s.split('/').collect::<Vec<_>>())
+    }
+}
+
+impl fmt::Display for DenseGrid<bool> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.render(|&p| if p { '█' } else { ' ' }) {
+            writeln!(f)?;
+            write!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitmap() {
+        let bitmap = Bitmap::from_rows(&["#..", ".#."]).unwrap();
+        assert_eq!((bitmap.width(), bitmap.height()), (3, 2));
+        assert!(bitmap.lit(0, 0) && bitmap.lit(1, 1));
+        assert!(!bitmap.lit(1, 0) && !bitmap.lit(5, 5));
+        assert_eq!(bitmap.encode(), "#../.#.");
+        assert_eq!(Bitmap::decode("#../.#."), Some(bitmap.clone()));
+        assert_eq!(bitmap.to_string(), "\n█  \n █ ");
+        assert_eq!(Bitmap::decode("#../.#"), None);
+    }
+}
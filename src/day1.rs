@@ -1,6 +1,12 @@
+use super::error::InputError;
+
 #[aoc_generator(day1)]
-fn input_generator(input: &str) -> Vec<u32> {
-    input.lines().map(|x| x.parse::<u32>().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<u32>, InputError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(l, x)| InputError::parse(1, l + 1, 1 + x.len() - x.trim_start().len(), x.trim()))
+        .collect()
 }
 
 fn compute_mass(x: u32) -> u32 {
@@ -22,18 +28,18 @@ fn compute_total_mass(x: u32) -> u32 {
 }
 
 #[aoc(day1, part1)]
-fn part1(input: &[u32]) -> u32 {
+pub fn part1(input: &[u32]) -> u32 {
     input.iter().map(|x| compute_mass(*x)).sum()
 }
 
 #[aoc(day1, part2)]
-fn part2(input: &[u32]) -> u32 {
+pub fn part2(input: &[u32]) -> u32 {
     input.iter().map(|x| compute_total_mass(*x)).sum()
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{compute_mass, compute_total_mass};
+    use super::{compute_mass, compute_total_mass, input_generator};
 
     #[test]
     fn test_part1() {
@@ -49,4 +55,14 @@ pub mod tests {
         assert_eq!(compute_total_mass(1969), 966);
         assert_eq!(compute_total_mass(10_0756), 50346);
     }
+
+    #[test]
+    fn test_input_generator() {
+        assert_eq!(input_generator("12\n14"), Ok(vec![12, 14]));
+        let e = input_generator("12\n1 4").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 1, line 2, column 1: invalid number \"1 4\""
+        );
+    }
 }
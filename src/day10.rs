@@ -1,22 +1,32 @@
+use super::error::InputError;
+use super::grid::{DenseGrid, GridError};
 use num::rational::Ratio;
 use std::collections::{BTreeMap, HashSet};
 
 #[aoc_generator(day10)]
-fn input_generator(input: &str) -> HashSet<Position> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(j, s)| {
-            s.chars()
-                .enumerate()
-                .filter(|&(_, c)| c == '#')
-                .map(move |(i, _)| Position::new(i as i64, j as i64))
-        })
-        .collect()
+pub fn input_generator(input: &str) -> Result<HashSet<Position>, InputError> {
+    let map = DenseGrid::parse(input, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })
+    .map_err(|e| match e {
+        GridError::Cell {
+            line,
+            column,
+            found,
+        } => InputError::at(10, line, column, format!("invalid cell {:?}", found)),
+        GridError::Ragged { line } => InputError::at(10, line, 1, "line of a different width"),
+    })?;
+    Ok(map
+        .iter()
+        .filter(|&(_, _, &asteroid)| asteroid)
+        .map(|(x, y, _)| Position::new(x as i64, y as i64))
+        .collect())
 }
 
 #[derive(PartialEq, Copy, Clone, Debug, Eq, Hash)]
-struct Position {
+pub struct Position {
     x: i64,
     y: i64,
 }
@@ -27,7 +37,7 @@ impl Position {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, PartialOrd)]
+#[derive(Debug, PartialEq, Eq, Hash)]
 struct Angle {
     x_sign: i64,
     ratio: Ratio<i64>,
@@ -46,6 +56,12 @@ impl Angle {
     }
 }
 
+impl std::cmp::PartialOrd for Angle {
+    fn partial_cmp(&self, other: &Angle) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl std::cmp::Ord for Angle {
     fn cmp(&self, other: &Angle) -> std::cmp::Ordering {
         // println!("cmp {:?} {:?}", self, other);
@@ -77,7 +93,7 @@ impl std::cmp::Ord for Angle {
     }
 }
 
-fn get_best_position(asteroids: &HashSet<Position>) -> (Position, usize) {
+fn get_best_position(asteroids: &HashSet<Position>) -> Option<(Position, usize)> {
     asteroids
         .iter()
         .map(|pos| {
@@ -93,17 +109,37 @@ fn get_best_position(asteroids: &HashSet<Position>) -> (Position, usize) {
             (*pos, nb.len())
         })
         .max_by_key(|(_, nb)| *nb)
-        .unwrap()
+}
+
+fn no_asteroid() -> InputError {
+    InputError::new(10, "no asteroid for the station")
 }
 
 #[aoc(day10, part1)]
-fn part1(asteroids: &HashSet<Position>) -> usize {
-    get_best_position(asteroids).1
+pub fn part1(asteroids: &HashSet<Position>) -> Result<usize, InputError> {
+    get_best_position(asteroids)
+        .map(|(_, nb)| nb)
+        .ok_or_else(no_asteroid)
 }
 
 #[aoc(day10, part2)]
-fn part2(asteroids: &HashSet<Position>) -> usize {
-    let station = get_best_position(asteroids).0;
+pub fn part2(asteroids: &HashSet<Position>) -> Result<usize, InputError> {
+    if asteroids.len() <= 200 {
+        return Err(InputError::new(
+            10,
+            format!(
+                "only {} asteroids to vaporize",
+                asteroids.len().saturating_sub(1)
+            ),
+        ));
+    }
+    let pos = vaporize(asteroids)?.1[199];
+    Ok((pos.x * 100 + pos.y) as usize)
+}
+
+/// The station and the other asteroids in the order the laser vaporizes them
+fn vaporize(asteroids: &HashSet<Position>) -> Result<(Position, Vec<Position>), InputError> {
+    let station = get_best_position(asteroids).ok_or_else(no_asteroid)?.0;
     let mut directions: BTreeMap<Angle, Vec<Position>> = BTreeMap::new();
     for (a, p) in asteroids
         .iter()
@@ -115,18 +151,52 @@ fn part2(asteroids: &HashSet<Position>) -> usize {
     for line in directions.values_mut() {
         line.sort_by_key(|p| -(p.x - station.x).abs() - (p.y - station.y).abs());
     }
-    let mut index = 0;
-    loop {
+    let mut order = vec![];
+    while order.len() + 1 < asteroids.len() {
         for line in directions.values_mut() {
             if let Some(pos) = line.pop() {
-                index += 1;
-                if index == 200 {
-                    // println!("INDEX 200 IS {}", pos.x * 100 + pos.y);
-                    return (pos.x * 100 + pos.y) as usize;
-                }
+                order.push(pos);
             }
         }
     }
+    Ok((station, order))
+}
+
+/// Cell of the recorded frames of the laser sweep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sky {
+    Empty,
+    Asteroid,
+    Station,
+    /// The asteroid being vaporized
+    Laser,
+}
+
+/// The map every `every` vaporized asteroids, from the first one to the
+/// last one
+pub fn record(
+    asteroids: &HashSet<Position>,
+    every: usize,
+) -> Result<Vec<DenseGrid<Sky>>, InputError> {
+    let (station, order) = vaporize(asteroids)?;
+    let width = asteroids.iter().map(|p| p.x + 1).max().unwrap_or(0) as usize;
+    let height = asteroids.iter().map(|p| p.y + 1).max().unwrap_or(0) as usize;
+    let mut map = DenseGrid::filled(width, height, Sky::Empty);
+    for p in asteroids {
+        map.set(p.x as usize, p.y as usize, Sky::Asteroid);
+    }
+    map.set(station.x as usize, station.y as usize, Sky::Station);
+    let mut frames = vec![];
+    for (i, p) in order.iter().enumerate() {
+        let (x, y) = (p.x as usize, p.y as usize);
+        if i % every.max(1) == 0 || i + 1 == order.len() {
+            map.set(x, y, Sky::Laser);
+            frames.push(map.clone());
+        }
+        map.set(x, y, Sky::Empty);
+    }
+    frames.push(map);
+    Ok(frames)
 }
 
 #[cfg(test)]
@@ -140,8 +210,8 @@ pub mod tests {
 #####
 ....#
 ...##";
-        let input = &input_generator(input);
-        assert_eq!(get_best_position(input), (Position::new(3, 4), 8));
+        let input = &input_generator(input).unwrap();
+        assert_eq!(get_best_position(input), Some((Position::new(3, 4), 8)));
         let input = "......#.#.
 #..#.#....
 ..#######.
@@ -152,8 +222,8 @@ pub mod tests {
 .##.#..###
 ##...#..#.
 .#....####";
-        let input = &input_generator(input);
-        assert_eq!(get_best_position(input), (Position::new(5, 8), 33));
+        let input = &input_generator(input).unwrap();
+        assert_eq!(get_best_position(input), Some((Position::new(5, 8), 33)));
         let input = "#.#...#.#.
 .###....#.
 .#....#...
@@ -164,8 +234,8 @@ pub mod tests {
 ..##....##
 ......#...
 .####.###.";
-        let input = &input_generator(input);
-        assert_eq!(get_best_position(input), (Position::new(1, 2), 35));
+        let input = &input_generator(input).unwrap();
+        assert_eq!(get_best_position(input), Some((Position::new(1, 2), 35)));
         let input = ".#..#..###
 ####.###.#
 ....###.#.
@@ -176,8 +246,8 @@ pub mod tests {
 #..#.#.###
 .##...##.#
 .....#.#..";
-        let input = &input_generator(input);
-        assert_eq!(get_best_position(input), (Position::new(6, 3), 41));
+        let input = &input_generator(input).unwrap();
+        assert_eq!(get_best_position(input), Some((Position::new(6, 3), 41)));
         let input = ".#..##.###...#######
 ##.############..##.
 .#.######.########.#
@@ -198,8 +268,24 @@ pub mod tests {
 .#.#.###########.###
 #.#.#.#####.####.###
 ###.##.####.##.#..##";
-        let input = &input_generator(input);
-        assert_eq!(get_best_position(input), (Position::new(11, 13), 210));
-        assert_eq!(part2(input), 802);
+        let input = &input_generator(input).unwrap();
+        assert_eq!(get_best_position(input), Some((Position::new(11, 13), 210)));
+        assert_eq!(part2(input), Ok(802));
+        let frames = record(input, 50).unwrap();
+        // 299 asteroids vaporized, the last one and the empty sky
+        assert_eq!(frames.len(), 8);
+        assert_eq!(frames[0].get(11, 12), Some(&Sky::Laser));
+        assert_eq!(frames[0].get(11, 13), Some(&Sky::Station));
+        assert!(frames[7].iter().all(|(_, _, &c)| c != Sky::Asteroid));
+    }
+
+    #[test]
+    fn test_input_generator() {
+        let e = input_generator(".#\n#X").unwrap_err();
+        assert_eq!(e.to_string(), "day 10, line 2, column 2: invalid cell 'X'");
+        let input = &input_generator("..\n..").unwrap();
+        assert!(part1(input).is_err());
+        let input = &input_generator(".#\n##").unwrap();
+        assert!(part2(input).is_err());
     }
 }
@@ -1,99 +1,154 @@
-use super::intcode::{ExitCode, IntCode};
-use std::{
-    cmp::{max, min},
-    collections::HashMap,
-};
+use super::bitmap::Bitmap;
+use super::error::InputError;
+use super::grid::{crop_all, DenseGrid, SparseGrid};
+use super::intcode::parse_program;
+use super::registry::Answer;
+use super::robot::{Control, Direction, Protocol, Robot, Runner, Turn};
 
 #[aoc_generator(day11)]
-pub fn input_generator(input: &str) -> Vec<i64> {
-    input
-        .split(',')
-        .map(|x| x.parse::<i64>().unwrap())
-        .collect()
+pub fn input_generator(input: &str) -> Result<Vec<i64>, InputError> {
+    parse_program(input).map_err(|e| InputError::program(11, e))
 }
 
-fn get_dir(current_dir: char, indice: i64) -> char {
-    if indice == 0 {
-        match current_dir {
-            'U' => 'L',
-            'L' => 'D',
-            'D' => 'R',
-            'R' => 'U',
-            _ => unreachable!(),
-        }
-    } else {
-        match current_dir {
-            'U' => 'R',
-            'R' => 'D',
-            'D' => 'L',
-            'L' => 'U',
-            _ => unreachable!(),
+/// The program reads the colour of the panel under the robot, black when
+/// not painted yet, then paints it and turns
+struct Painter;
+
+impl Protocol for Painter {
+    type Cell = i64;
+    const ARITY: usize = 2;
+
+    fn encode(&mut self, robot: &Robot<i64>) -> i64 {
+        *robot.here().unwrap_or(&0)
+    }
+
+    fn decode(&mut self, robot: &mut Robot<i64>, outputs: &[i64]) -> Result<Control, String> {
+        let turn = match outputs[1] {
+            0 => Turn::Left,
+            1 => Turn::Right,
+            t => return Err(format!("invalid turn {}", t)),
+        };
+        match outputs[0] {
+            colour @ 0..=1 => robot.mark(colour),
+            colour => return Err(format!("invalid colour {}", colour)),
         }
+        robot.turn(turn);
+        robot.advance();
+        Ok(Control::Continue)
     }
 }
 
-fn paint(input: &[i64], start_value: i64) -> HashMap<(i32, i32), i64> {
-    let mut map = HashMap::new();
-    let mut robot = IntCode::new(input.to_vec(), vec![]);
-    map.insert((0, 0), start_value);
-
-    let (mut x, mut y, mut dir) = (0, 0, 'U');
-
-    loop {
-        match robot.execute() {
-            ExitCode::Stop => break,
-            ExitCode::Output(c) => {
-                if let ExitCode::Output(d) = robot.execute() {
-                    map.insert((x, y), c);
-                    dir = get_dir(dir, d);
-                    match dir {
-                        'U' => y += 1,
-                        'L' => x += 1,
-                        'D' => y -= 1,
-                        'R' => x -= 1,
-                        _ => unreachable!(),
-                    }
-                } else {
-                    unreachable!()
-                }
-            }
-            ExitCode::AwaitInput => robot.push_input(*map.get(&(x, y)).unwrap_or(&0)),
-            _ => unreachable!(),
-        }
+/// Panels painted by the robot, `start_value` being the colour of the panel
+/// it starts on, 0 for black and 1 for white. A white start panel is in the
+/// grid even if the robot never paints it.
+pub fn paint(input: &[i64], start_value: i64) -> Result<SparseGrid<i64>, InputError> {
+    paint_with(input, start_value, |_, _| {})
+}
+
+/// Paint the hull, `step` seeing the panels and the robot position after
+/// each move
+fn paint_with<F>(
+    input: &[i64],
+    start_value: i64,
+    mut step: F,
+) -> Result<SparseGrid<i64>, InputError>
+where
+    F: FnMut(&SparseGrid<i64>, (i64, i64)),
+{
+    let mut robot = Robot::new(Direction::Up);
+    // Unknown panels are black
+    if start_value != 0 {
+        robot.mark(start_value);
     }
-    map
+    let mut runner = Runner::new(input, robot, Painter);
+    runner
+        .run_with(|robot| step(&robot.grid, robot.position))
+        .map_err(|e| InputError::new(11, e.to_string()))?;
+    Ok(runner.robot.grid)
 }
 
-fn display_map(map: &HashMap<(i32, i32), i64>) {
-    let (x_min, x_max, y_min, y_max) =
-        map.keys()
-            .fold((0, 0, 0, 0), |(x_min, x_max, y_min, y_max), &(x, y)| {
-                let x_min = min(x, x_min);
-                let x_max = max(x, x_max);
-                let y_min = min(y, y_min);
-                let y_max = max(y, y_max);
-                (x_min, x_max, y_min, y_max)
-            });
-    println!("{}-{} {}-{}", x_min, x_max, y_min, y_max);
-    for y in y_min..=y_max {
-        for x in x_min..=x_max {
-            let v = map.get(&(x, y)).unwrap_or(&0);
-            print!("{}", if v == &1 { '█' } else { ' ' })
+/// Cell of the recorded frames standing for the robot, panels being 0 for
+/// black and 1 for white
+pub const ROBOT: i64 = 2;
+
+/// The panels after every `every` moves of the robot and once it stopped
+pub fn record(
+    input: &[i64],
+    start_value: i64,
+    every: usize,
+) -> Result<Vec<DenseGrid<i64>>, InputError> {
+    let mut frames = vec![];
+    let mut moves = 0;
+    let hull = paint_with(input, start_value, |map, (x, y)| {
+        if moves % every.max(1) == 0 {
+            let mut frame = map.clone();
+            frame.set(x, y, ROBOT);
+            frames.push(frame);
         }
-        println!()
-    }
+        moves += 1;
+    })?;
+    frames.push(hull);
+    Ok(crop_all(&frames, 0))
+}
+
+/// The white panels
+pub fn to_bitmap(panels: &SparseGrid<i64>) -> Bitmap {
+    panels.to_dense(0).map(|&v| v == 1)
 }
 
 #[aoc(day11, part1)]
-fn part1(input: &[i64]) -> usize {
-    let map = paint(input, 0);
-    display_map(&map);
-    map.len()
+pub fn part1(input: &[i64]) -> Result<usize, InputError> {
+    painted_panels(input)
 }
 
 #[aoc(day11, part2)]
-fn part2(input: &[i64]) -> usize {
-    let map = paint(input, 1);
-    display_map(&map);
-    map.len()
+pub fn part2(input: &[i64]) -> Result<Answer, InputError> {
+    Ok(Answer::Image(hull(input)?))
+}
+
+/// Panels painted at least once when starting on a black panel
+pub fn painted_panels(input: &[i64]) -> Result<usize, InputError> {
+    Ok(paint(input, 0)?.len())
+}
+
+/// The registration identifier painted when starting on a white panel
+pub fn hull(input: &[i64]) -> Result<Bitmap, InputError> {
+    Ok(to_bitmap(&paint(input, 1)?))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::ocr::recognize;
+    use crate::registry::load_input;
+
+    #[test]
+    fn test_hull() {
+        let input = input_generator(&load_input(11).unwrap()).unwrap();
+        assert_eq!(painted_panels(&input), Ok(2276));
+        // Read as painted, without flipping the image
+        assert_eq!(
+            recognize(&hull(&input).unwrap()),
+            Ok("CBLPJZCU".to_string())
+        );
+        let frames = record(&input, 1, 100).unwrap();
+        assert_eq!(frames.last().map(to_image), hull(&input).ok());
+    }
+
+    #[test]
+    fn test_start_panel() {
+        // Stop without painting anything
+        let panels = paint(&[99], 1).unwrap();
+        assert_eq!(panels.get(0, 0), Some(&1));
+        assert_eq!(panels.len(), 1);
+        assert!(paint(&[99], 0).unwrap().is_empty());
+        // Report the colour read as the paint, then stop
+        let panels = paint(&[3, 9, 4, 9, 104, 0, 99, 0, 0, 0], 1).unwrap();
+        assert_eq!(panels.get(0, 0), Some(&1));
+        assert_eq!(panels.len(), 1);
+    }
+
+    fn to_image(frame: &DenseGrid<i64>) -> Bitmap {
+        frame.map(|&v| v == 1)
+    }
 }
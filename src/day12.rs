@@ -1,14 +1,56 @@
+use super::error::InputError;
+use super::grid::DenseGrid;
 use num::integer::lcm;
 use std::cmp::Ordering;
 
+/// One moon per line, e.g. `<x=-1, y=0, z=2>`
 #[aoc_generator(day12)]
-fn input_generator(_: &str) -> Vec<Moon> {
-    vec![
-        Moon::new(-1, -4, 0),
-        Moon::new(4, 7, -1),
-        Moon::new(-14, -10, 9),
-        Moon::new(1, 2, 17),
-    ]
+pub fn input_generator(input: &str) -> Result<Vec<Moon>, InputError> {
+    let mut moons = vec![];
+    for (l, line) in input.lines().enumerate() {
+        let (line, l) = (line.trim_end(), l + 1);
+        let inner = line
+            .strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .ok_or_else(|| {
+                InputError::at(
+                    12,
+                    l,
+                    1,
+                    format!("expected <x=.., y=.., z=..>, got {:?}", line),
+                )
+            })?;
+        let mut axes = [0; 3];
+        let mut column = 2;
+        let fields: Vec<&str> = inner.split(',').collect();
+        if fields.len() != 3 {
+            return Err(InputError::at(
+                12,
+                l,
+                1,
+                format!("expected 3 coordinates, got {}", fields.len()),
+            ));
+        }
+        for ((field, axis), name) in fields.iter().zip(axes.iter_mut()).zip(&["x", "y", "z"]) {
+            let start = column + field.len() - field.trim_start().len();
+            let value = field
+                .trim()
+                .strip_prefix(name)
+                .and_then(|s| s.strip_prefix('='))
+                .ok_or_else(|| {
+                    InputError::at(
+                        12,
+                        l,
+                        start,
+                        format!("expected {}=.., got {:?}", name, field.trim()),
+                    )
+                })?;
+            *axis = InputError::parse(12, l, start + 2, value)?;
+            column += field.len() + 1;
+        }
+        moons.push(Moon::new(axes[0], axes[1], axes[2]));
+    }
+    Ok(moons)
 }
 
 #[derive(Clone, Debug)]
@@ -25,7 +67,7 @@ impl Pos3D {
 }
 
 #[derive(Clone, Debug)]
-struct Moon {
+pub struct Moon {
     pos: Pos3D,
     velocity: Pos3D,
 }
@@ -83,22 +125,58 @@ fn cmp_axis(a: i64, b: i64) -> i64 {
     }
 }
 
+fn step(moons: &mut [Moon]) {
+    for i in 0..moons.len() {
+        for j in (i + 1)..moons.len() {
+            let d = moons[i].compare(&moons[j]);
+            moons[i].add_velocity(&d);
+            moons[j].remove_velocity(&d);
+        }
+        moons[i].update_gravity()
+    }
+}
+
 fn simulate(mut moons: Vec<Moon>, steps: u32) -> i64 {
     for _ in 1..=steps {
-        // println!("Step {}", k);
-        for i in 0..moons.len() {
-            for j in (i + 1)..moons.len() {
-                let d = moons[i].compare(&moons[j]);
-                moons[i].add_velocity(&d);
-                moons[j].remove_velocity(&d);
-            }
-            moons[i].update_gravity()
-        }
+        step(&mut moons);
     }
 
     moons.iter().map(|m| m.get_energy()).sum()
 }
 
+/// The moons seen from above, scaled to fit a square of `size` cells, at
+/// the start and after each step; cells hold the index of the moon plus one
+/// and 0 when empty
+pub fn record(moons: &[Moon], steps: usize, size: usize) -> Vec<DenseGrid<usize>> {
+    let mut moons = moons.to_vec();
+    let mut positions = vec![];
+    for k in 0..=steps {
+        if k > 0 {
+            step(&mut moons);
+        }
+        positions.push(moons.iter().map(|m| (m.pos.x, m.pos.y)).collect::<Vec<_>>());
+    }
+    let extent = positions
+        .iter()
+        .flatten()
+        .map(|&(x, y)| x.abs().max(y.abs()))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let size = size.max(1);
+    let cell = |v: i64| ((v + extent) * (size as i64 - 1) / (2 * extent)) as usize;
+    positions
+        .iter()
+        .map(|moons| {
+            let mut frame = DenseGrid::filled(size, size, 0);
+            for (i, &(x, y)) in moons.iter().enumerate() {
+                frame.set(cell(x), cell(y), i + 1);
+            }
+            frame
+        })
+        .collect()
+}
+
 fn simulate_one_axis(values: &[i64]) -> usize {
     let mut values: Vec<(i64, i64)> = values.iter().map(|&v| (v, 0)).collect();
     let init = values.clone();
@@ -119,12 +197,12 @@ fn simulate_one_axis(values: &[i64]) -> usize {
 }
 
 #[aoc(day12, part1)]
-fn part1(input: &[Moon]) -> i64 {
+pub fn part1(input: &[Moon]) -> i64 {
     simulate(input.to_vec(), 1000)
 }
 
 #[aoc(day12, part2)]
-fn part2(input: &[Moon]) -> usize {
+pub fn part2(input: &[Moon]) -> usize {
     let x = simulate_one_axis(&input.iter().map(|m| m.pos.x).collect::<Vec<_>>());
     let y = simulate_one_axis(&input.iter().map(|m| m.pos.y).collect::<Vec<_>>());
     let z = simulate_one_axis(&input.iter().map(|m| m.pos.z).collect::<Vec<_>>());
@@ -135,6 +213,26 @@ fn part2(input: &[Moon]) -> usize {
 pub mod tests {
     use super::*;
 
+    #[test]
+    fn test_input_generator() {
+        let input = input_generator("<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>").unwrap();
+        assert_eq!(simulate(input, 0), 0);
+        let input = input_generator(
+            "<x=-1, y=0, z=2>\n<x=2, y=-10, z=-7>\n<x=4, y=-8, z=8>\n<x=3, y=5, z=-1>",
+        )
+        .unwrap();
+        assert_eq!(simulate(input, 10), 179);
+        let e = input_generator("<x=-1, y=0, z=2>\n<x=2, w=-10, z=-7>").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 12, line 2, column 7: expected y=.., got \"w=-10\""
+        );
+        let e = input_generator("<x=-1, y=a, z=2>").unwrap_err();
+        assert_eq!(e.location.map(|l| l.column), Some(10));
+        assert!(input_generator("<x=-1, y=0>").is_err());
+        assert!(input_generator("x=-1, y=0, z=2").is_err());
+    }
+
     #[test]
     fn test_part1() {
         let input = vec![
@@ -150,7 +248,14 @@ pub mod tests {
             Moon::new(2, -7, 3),
             Moon::new(9, -8, -3),
         ];
-        assert_eq!(simulate(input, 100), 1940);
+        assert_eq!(simulate(input.clone(), 100), 1940);
+        let frames = record(&input, 10, 21);
+        assert_eq!(frames.len(), 11);
+        assert_eq!(frames[10].iter().filter(|&(_, _, &m)| m > 0).count(), 4);
+        // Seen from -10 to 10, one cell per unit
+        let frame = &record(&input, 0, 21)[0];
+        assert_eq!(frame.get(2, 0), Some(&1));
+        assert_eq!(frame.get(19, 2), Some(&4));
     }
 
     #[test]
@@ -1,12 +1,11 @@
-use super::intcode::{ExitCode, IntCode};
-use std::cmp::Ordering;
+use super::error::InputError;
+use super::grid::{crop_all, DenseGrid, SparseGrid};
+use super::intcode::{parse_program, Counter, ExitCode, IntCode};
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
 
 #[aoc_generator(day13)]
-pub fn input_generator(input: &str) -> Vec<i64> {
-    input
-        .split(',')
-        .map(|x| x.parse::<i64>().unwrap())
-        .collect()
+pub fn input_generator(input: &str) -> Result<Vec<i64>, InputError> {
+    parse_program(input).map_err(|e| InputError::program(13, e))
 }
 
 pub fn cmp_pb(b: i64, p: i64) -> i64 {
@@ -17,51 +16,461 @@ pub fn cmp_pb(b: i64, p: i64) -> i64 {
     }
 }
 
-#[aoc(day13, part1)]
-pub fn part1(input: &[i64]) -> u32 {
-    let mut i = IntCode::new(input.to_vec(), vec![]);
-    let mut block_tile = 0;
-    let mut j = 0;
-    loop {
-        match i.execute() {
-            ExitCode::Output(o) => {
-                if o == 2 && j == 2 {
-                    block_tile += 1
-                };
-                j = (j + 1) % 3
+fn aborted(code: ExitCode) -> InputError {
+    InputError::new(13, format!("arcade program aborted with {:?}", code))
+}
+
+pub const EMPTY: i64 = 0;
+pub const WALL: i64 = 1;
+pub const BLOCK: i64 = 2;
+pub const PADDLE: i64 = 3;
+pub const BALL: i64 = 4;
+
+/// What the arcade program stopped for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Waiting for the joystick position
+    Joystick,
+    GameOver,
+}
+
+/// The cabinet running the program, drawing its `x, y, tile` output triples
+/// on the screen and `-1, 0, score` ones on the score display
+#[derive(Debug)]
+pub struct Arcade {
+    program: IntCode,
+    screen: SparseGrid<i64>,
+    score: i64,
+    ball: (i64, i64),
+    paddle: (i64, i64),
+    /// Ball position when the joystick was last read
+    last_ball: Option<(i64, i64)>,
+    velocity: Option<(i64, i64)>,
+    output: Vec<i64>,
+}
+
+impl Arcade {
+    /// Playing needs quarters, `free_play` inserts them at address 0
+    pub fn new(input: &[i64], free_play: bool) -> Result<Self, InputError> {
+        if input.is_empty() {
+            return Err(InputError::new(13, "empty arcade program"));
+        }
+        let mut input = input.to_vec();
+        if free_play {
+            input[0] = 2;
+        }
+        Ok(Arcade {
+            program: IntCode::new(input, vec![]),
+            screen: SparseGrid::new(),
+            score: 0,
+            ball: (0, 0),
+            paddle: (0, 0),
+            last_ball: None,
+            velocity: None,
+            output: vec![],
+        })
+    }
+
+    /// Run until the joystick is read or the game is over
+    pub fn run(&mut self) -> Result<Status, InputError> {
+        loop {
+            match self.program.execute() {
+                ExitCode::Output(o) => {
+                    self.output.push(o);
+                    if let [x, y, value] = self.output[..] {
+                        self.draw(x, y, value);
+                        self.output.clear();
+                    }
+                }
+                ExitCode::AwaitInput => {
+                    if let Some((x, y)) = self.last_ball {
+                        self.velocity = Some((self.ball.0 - x, self.ball.1 - y));
+                    }
+                    self.last_ball = Some(self.ball);
+                    return Ok(Status::Joystick);
+                }
+                ExitCode::Stop => return Ok(Status::GameOver),
+                code => return Err(aborted(code)),
+            }
+        }
+    }
+
+    fn draw(&mut self, x: i64, y: i64, value: i64) {
+        match (x, y, value) {
+            (-1, 0, score) => self.score = score,
+            (x, y, tile) => {
+                match tile {
+                    PADDLE => self.paddle = (x, y),
+                    BALL => self.ball = (x, y),
+                    _ => {}
+                }
+                self.screen.set(x, y, tile);
+            }
+        }
+    }
+
+    /// -1 to tilt left, 0 for neutral and 1 to tilt right
+    pub fn joystick(&mut self, position: i64) {
+        self.program.push_input(position)
+    }
+
+    pub fn screen(&self) -> &SparseGrid<i64> {
+        &self.screen
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    pub fn ball(&self) -> (i64, i64) {
+        self.ball
+    }
+
+    pub fn paddle(&self) -> (i64, i64) {
+        self.paddle
+    }
+
+    /// Move of the ball since the previous frame, unknown on the first one
+    pub fn velocity(&self) -> Option<(i64, i64)> {
+        self.velocity
+    }
+
+    /// Column where the falling ball will reach the row above the paddle,
+    /// bouncing on the side walls but ignoring the blocks on its way
+    pub fn landing(&self) -> Option<i64> {
+        let (mut dx, dy) = self.velocity?;
+        let bounds = self.screen.bounds()?;
+        if dy <= 0 {
+            return None;
+        }
+        let (left, right) = (bounds.x_min + 1, bounds.x_max - 1);
+        let mut x = self.ball.0;
+        for _ in self.ball.1..self.paddle.1 - 1 {
+            if !(left..=right).contains(&(x + dx)) {
+                dx = -dx;
             }
-            ExitCode::Stop => break,
-            _ => unreachable!(),
+            x += dx;
         }
+        Some(x)
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.screen.iter().filter(|&(_, _, &t)| t == BLOCK).count()
+    }
+
+    /// The screen as text, walls `█`, blocks `#`, the paddle `=` and the
+    /// ball `o`
+    pub fn render(&self) -> Vec<String> {
+        self.screen
+            .render(|tile| match tile.copied().unwrap_or(EMPTY) {
+                WALL => '█',
+                BLOCK => '#',
+                PADDLE => '=',
+                BALL => 'o',
+                _ => ' ',
+            })
+    }
+}
+
+/// Chooses where to tilt the joystick each time the arcade reads it
+pub trait Joystick {
+    /// -1 for left, 0 for neutral and 1 for right
+    fn position(&mut self, arcade: &Arcade) -> i64;
+}
+
+/// Keep the paddle under the ball
+pub struct Follow;
+
+impl Joystick for Follow {
+    fn position(&mut self, arcade: &Arcade) -> i64 {
+        cmp_pb(arcade.ball().0, arcade.paddle().0)
+    }
+}
+
+/// Move the paddle to where the ball will land once it is below the blocks,
+/// as a block could still deflect it out of reach above them, following the
+/// ball otherwise
+pub struct Intercept;
+
+impl Joystick for Intercept {
+    fn position(&mut self, arcade: &Arcade) -> i64 {
+        let lowest_block = arcade
+            .screen()
+            .iter()
+            .filter(|&(_, _, &t)| t == BLOCK)
+            .map(|(_, y, _)| y)
+            .max();
+        let target = match arcade.landing() {
+            Some(x) if lowest_block.is_none_or(|y| arcade.ball().1 > y) => x,
+            _ => arcade.ball().0,
+        };
+        cmp_pb(target, arcade.paddle().0)
+    }
+}
+
+/// Follow the ball, showing the arcade to `frame` first
+struct Watch<F>(F);
+
+impl<F: FnMut(&Arcade)> Joystick for Watch<F> {
+    fn position(&mut self, arcade: &Arcade) -> i64 {
+        (self.0)(arcade);
+        Follow.position(arcade)
+    }
+}
+
+/// Positions recorded during an earlier game, neutral once they run out
+pub struct Replay {
+    moves: Vec<i64>,
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(moves: Vec<i64>) -> Self {
+        Replay { moves, next: 0 }
     }
-    block_tile
+}
+
+impl Joystick for Replay {
+    fn position(&mut self, _arcade: &Arcade) -> i64 {
+        let position = self.moves.get(self.next).copied().unwrap_or(0);
+        self.next += 1;
+        position
+    }
+}
+
+/// How a game went
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// All the blocks are broken
+    pub won: bool,
+    pub score: i64,
+    /// Joystick reads
+    pub frames: usize,
+    pub instructions: usize,
+    /// Joystick positions, to replay the game
+    pub moves: Vec<i64>,
+}
+
+/// Play a game with free play until it is over
+pub fn play_with<J: Joystick + ?Sized>(
+    input: &[i64],
+    joystick: &mut J,
+) -> Result<Report, InputError> {
+    Ok(game(input, joystick)?.0)
+}
+
+/// The report of a game and the arcade once it is over
+fn game<J: Joystick + ?Sized>(
+    input: &[i64],
+    joystick: &mut J,
+) -> Result<(Report, Arcade), InputError> {
+    let mut arcade = Arcade::new(input, true)?;
+    let counter = Rc::new(RefCell::new(Counter::default()));
+    arcade.program.attach(counter.clone());
+    let mut moves = vec![];
+    while arcade.run()? == Status::Joystick {
+        let position = joystick.position(&arcade);
+        moves.push(position);
+        arcade.joystick(position);
+    }
+    let instructions = counter.borrow().0;
+    let report = Report {
+        won: arcade.blocks() == 0,
+        score: arcade.score(),
+        frames: moves.len(),
+        instructions,
+        moves,
+    };
+    Ok((report, arcade))
+}
+
+/// Tiles drawn by the program when no quarters are inserted
+pub fn screen(input: &[i64]) -> Result<SparseGrid<i64>, InputError> {
+    let mut arcade = Arcade::new(input, false)?;
+    match arcade.run()? {
+        Status::GameOver => Ok(arcade.screen),
+        Status::Joystick => Err(aborted(ExitCode::AwaitInput)),
+    }
+}
+
+#[aoc(day13, part1)]
+pub fn part1(input: &[i64]) -> Result<usize, InputError> {
+    let screen = screen(input)?;
+    Ok(screen.iter().filter(|&(_, _, &tile)| tile == BLOCK).count())
 }
 
 #[aoc(day13, part2)]
-pub fn part2(input: &[i64]) -> i64 {
-    let mut input = input.to_vec();
-    input[0] = 2;
-    let mut i = IntCode::new(input, vec![]);
-    let mut param = [0; 3];
-    let (mut score, mut px, mut bx, mut j) = (0, 0, 0, 0);
-    loop {
-        match i.execute() {
-            ExitCode::Output(o) => {
-                param[j] = o;
-                if j == 2 {
-                    match param {
-                        [-1, 0, s] => score = s,
-                        [x, _, 3] => px = x,
-                        [x, _, 4] => bx = x,
-                        _ => {}
-                    }
-                }
-                j = (j + 1) % 3
+pub fn part2(input: &[i64]) -> Result<i64, InputError> {
+    Ok(play_with(input, &mut Follow)?.score)
+}
+
+/// Sum of the points of the blocks read from the score table, which only
+/// works on programs whose score routine `analyse` recognises
+#[aoc(day13, part2, ScoreTable)]
+pub fn part2_score_table(input: &[i64]) -> Result<i64, InputError> {
+    let screen = screen(input)?;
+    let layout = layout(input, &screen)?;
+    Ok(screen
+        .iter()
+        .filter(|&(_, _, &tile)| tile == BLOCK)
+        .map(|(x, y, _)| input[layout.scores + layout.score_index(x, y)])
+        .sum())
+}
+
+/// Where the arcade program keeps the screen and the points of each block
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    /// Address of the tiles, row by row
+    pub map: usize,
+    /// Address of the points, the table being as large as the map
+    pub scores: usize,
+    /// Address of the routine reading the points of a block
+    pub routine: usize,
+    /// Points of the block at `x, y` are at index
+    /// `((x * height + y) * factor + offset) % modulus` of the table
+    pub height: i64,
+    pub factor: i64,
+    pub offset: i64,
+    pub modulus: i64,
+}
+
+impl Layout {
+    pub fn score_index(&self, x: i64, y: i64) -> usize {
+        ((x * self.height + y) * self.factor + self.offset).rem_euclid(self.modulus) as usize
+    }
+}
+
+/// Value stored by an `add` or `mul` of two immediates into a relative
+/// address, with that address
+fn constant(instruction: &[i64]) -> Option<(i64, i64)> {
+    match instruction {
+        [21101, a, b, slot] => Some((*slot, a + b)),
+        [21102, a, b, slot] => Some((*slot, a * b)),
+        _ => None,
+    }
+}
+
+/// The routine scoring a block gets `x` and `y` as arguments, computes
+/// `x * height + y` then calls a modulo routine with the factor, offset and
+/// modulus before reading the table.
+///
+/// The routine is recognised by its instructions as compiled in the known
+/// inputs, only the constants being free, so a program built differently
+/// gives `None`.
+fn score_routine(memory: &[i64], scores: usize) -> Option<(usize, [i64; 4])> {
+    const START: [i64; 6] = [-2, 1, 22201, 1, -1, 1];
+    const ROUTINE_LENGTH: usize = 40;
+    let read = [21201, 1, scores as i64, -2];
+    (0..memory.len()).find_map(|start| {
+        let code = memory.get(start..start + ROUTINE_LENGTH)?;
+        if code[0] != 22102 || code[2..8] != START {
+            return None;
+        }
+        let mut hash = [code[1], 0, 0, 0];
+        for instruction in code[8..20].chunks(4) {
+            let (slot, value) = constant(instruction)?;
+            match slot {
+                2..=4 => hash[slot as usize - 1] = value,
+                _ => return None,
             }
-            ExitCode::AwaitInput => i.push_input(cmp_pb(bx, px)),
-            ExitCode::Stop => break,
-            _ => unreachable!(),
         }
+        if hash[3] <= 0 || !code.windows(4).any(|w| w == read) {
+            return None;
+        }
+        Some((start, hash))
+    })
+}
+
+/// Find the tiles of the screen and the score table in the memory of the
+/// program, without playing, see `score_routine` for the programs it knows
+pub fn analyse(input: &[i64]) -> Result<Layout, InputError> {
+    layout(input, &screen(input)?)
+}
+
+fn layout(input: &[i64], screen: &SparseGrid<i64>) -> Result<Layout, InputError> {
+    let screen = screen.to_dense(EMPTY);
+    let tiles: Vec<i64> = screen.iter().map(|(_, _, &t)| t).collect();
+    let map = input
+        .windows(tiles.len().max(1))
+        .position(|w| w == &tiles[..])
+        .ok_or_else(|| InputError::new(13, "tile map not found in the program"))?;
+    let scores = map + tiles.len();
+    if input.len() < scores + tiles.len() {
+        return Err(InputError::new(13, "score table outside of the program"));
+    }
+    let (routine, [height, factor, offset, modulus]) = score_routine(input, scores)
+        .ok_or_else(|| InputError::new(13, "score routine not found in the program"))?;
+    if modulus as usize > tiles.len() {
+        return Err(InputError::new(13, "score index outside of the table"));
+    }
+    Ok(Layout {
+        map,
+        scores,
+        routine,
+        height,
+        factor,
+        offset,
+        modulus,
+    })
+}
+
+/// The screen every `every` joystick reads and once the game is over, the
+/// paddle following the ball
+pub fn record(input: &[i64], every: usize) -> Result<Vec<DenseGrid<i64>>, InputError> {
+    let mut frames = vec![];
+    let mut reads = 0;
+    let (_, last) = game(
+        input,
+        &mut Watch(|arcade: &Arcade| {
+            if reads % every.max(1) == 0 {
+                frames.push(arcade.screen.clone());
+            }
+            reads += 1;
+        }),
+    )?;
+    frames.push(last.screen);
+    Ok(crop_all(&frames, 0))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::registry::load_input;
+
+    #[test]
+    fn test_strategies() {
+        let input = input_generator(&load_input(13).unwrap()).unwrap();
+        let follow = play_with(&input, &mut Follow).unwrap();
+        assert!(follow.won);
+        assert_eq!(follow.score, 20940);
+        let intercept = play_with(&input, &mut Intercept).unwrap();
+        assert!(intercept.won);
+        assert_eq!(intercept.score, follow.score);
+        let replay = play_with(&input, &mut Replay::new(follow.moves.clone())).unwrap();
+        assert_eq!(replay, follow);
+        let idle = play_with(&input, &mut Replay::new(vec![])).unwrap();
+        assert!(!idle.won && idle.frames < follow.frames);
+        assert_eq!(
+            play_with(&[], &mut Follow).unwrap_err().to_string(),
+            "day 13: empty arcade program"
+        );
+    }
+
+    #[test]
+    fn test_score_table() {
+        let input = input_generator(&load_input(13).unwrap()).unwrap();
+        let layout = analyse(&input).unwrap();
+        assert_eq!(layout.scores - layout.map, 44 * 24);
+        assert_eq!(part2_score_table(&input), part2(&input));
+
+        // Without its score routine the program cannot be analysed
+        let mut broken = input.clone();
+        broken[layout.routine] = 99;
+        assert_eq!(score_routine(&broken, layout.scores), None);
+        assert_eq!(
+            analyse(&broken).unwrap_err().to_string(),
+            "day 13: score routine not found in the program"
+        );
+        assert!(part2_score_table(&broken).is_err());
     }
-    score
 }
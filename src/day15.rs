@@ -0,0 +1,260 @@
+use super::error::InputError;
+use super::grid::{SparseGrid, ORTHOGONAL};
+use super::intcode::parse_program;
+use super::robot::{Control, Direction, Protocol, Robot, Runner};
+use std::collections::{HashMap, VecDeque};
+
+/// Not registered with aoc-runner, which embeds the input of every day it
+/// runs, until the puzzle input is checked in
+pub fn input_generator(input: &str) -> Result<Vec<i64>, InputError> {
+    parse_program(input).map_err(|e| InputError::program(15, e))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+/// Movement commands are north, south, west and east, north being up
+fn command(direction: Direction) -> i64 {
+    match direction {
+        Direction::Up => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+        Direction::Right => 4,
+    }
+}
+
+fn next(position: (i64, i64), direction: Direction) -> (i64, i64) {
+    let (dx, dy) = direction.delta();
+    (position.0 + dx, position.1 + dy)
+}
+
+/// Depth first exploration, the droid backtracking once every neighbour of
+/// its position is known
+#[derive(Default)]
+struct Explorer {
+    /// Moves from the start to the droid
+    path: Vec<Direction>,
+    /// Last move asked and whether it was backtracking
+    last: Option<(Direction, bool)>,
+}
+
+impl Explorer {
+    fn unknown(robot: &Robot<Tile>) -> Option<Direction> {
+        Direction::ALL.iter().copied().find(|&d| {
+            let (x, y) = next(robot.position, d);
+            robot.grid.get(x, y).is_none()
+        })
+    }
+}
+
+impl Protocol for Explorer {
+    type Cell = Tile;
+    const ARITY: usize = 1;
+
+    fn encode(&mut self, robot: &Robot<Tile>) -> i64 {
+        let (direction, backtracking) = match Explorer::unknown(robot) {
+            Some(d) => (d, false),
+            // The exploration stops before the droid is back at the start
+            None => (
+                self.path.pop().map_or(Direction::Up, Direction::reverse),
+                true,
+            ),
+        };
+        self.last = Some((direction, backtracking));
+        command(direction)
+    }
+
+    fn decode(&mut self, robot: &mut Robot<Tile>, outputs: &[i64]) -> Result<Control, String> {
+        let (direction, backtracking) = self.last.ok_or("status before any move")?;
+        let tile = match outputs[0] {
+            0 => Tile::Wall,
+            1 => Tile::Open,
+            2 => Tile::Oxygen,
+            s => return Err(format!("invalid status {}", s)),
+        };
+        match (tile, backtracking) {
+            (Tile::Wall, true) => return Err("wall on the way back".to_string()),
+            (Tile::Wall, false) => {
+                let (x, y) = next(robot.position, direction);
+                robot.grid.set(x, y, Tile::Wall);
+            }
+            (_, _) => {
+                robot.heading = direction;
+                robot.advance();
+                robot.mark(tile);
+                if !backtracking {
+                    self.path.push(direction);
+                }
+            }
+        }
+        if self.path.is_empty() && Explorer::unknown(robot).is_none() {
+            Ok(Control::Stop)
+        } else {
+            Ok(Control::Continue)
+        }
+    }
+}
+
+/// Every tile the droid can reach, the droid starting at the origin
+pub fn explore(program: &[i64]) -> Result<SparseGrid<Tile>, InputError> {
+    let mut robot = Robot::new(Direction::Up);
+    robot.mark(Tile::Open);
+    let mut runner = Runner::new(program, robot, Explorer::default());
+    runner
+        .run()
+        .map_err(|e| InputError::new(15, e.to_string()))?;
+    Ok(runner.robot.grid)
+}
+
+/// Length of the shortest path to every reachable tile
+fn distances(map: &SparseGrid<Tile>, from: (i64, i64)) -> HashMap<(i64, i64), usize> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    distances.insert(from, 0);
+    queue.push_back(from);
+    while let Some((x, y)) = queue.pop_front() {
+        let d = distances[&(x, y)];
+        for (nx, ny, &tile) in map.neighbours(x, y, &ORTHOGONAL) {
+            if tile != Tile::Wall && !distances.contains_key(&(nx, ny)) {
+                distances.insert((nx, ny), d + 1);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    distances
+}
+
+fn oxygen(map: &SparseGrid<Tile>) -> Result<(i64, i64), InputError> {
+    map.iter()
+        .find(|&(_, _, &tile)| tile == Tile::Oxygen)
+        .map(|(x, y, _)| (x, y))
+        .ok_or_else(|| InputError::new(15, "no oxygen system found"))
+}
+
+/// Fewest moves from the start to the oxygen system
+pub fn part1(input: &[i64]) -> Result<usize, InputError> {
+    let map = explore(input)?;
+    Ok(distances(&map, (0, 0))[&oxygen(&map)?])
+}
+
+/// Minutes for the oxygen to fill the area
+pub fn part2(input: &[i64]) -> Result<usize, InputError> {
+    let map = explore(input)?;
+    let filled = distances(&map, oxygen(&map)?);
+    Ok(filled.values().copied().max().unwrap_or(0))
+}
+
+/// The explored area, `D` being the start of the droid and `O` the oxygen
+/// system
+pub fn render(map: &SparseGrid<Tile>) -> Vec<String> {
+    let bounds = match map.bounds() {
+        Some(b) => b,
+        None => return vec![],
+    };
+    (bounds.y_min..=bounds.y_max)
+        .map(|y| {
+            (bounds.x_min..=bounds.x_max)
+                .map(|x| match map.get(x, y) {
+                    _ if (x, y) == (0, 0) => 'D',
+                    Some(Tile::Wall) => '#',
+                    Some(Tile::Open) => '.',
+                    Some(Tile::Oxygen) => 'O',
+                    None => ' ',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const MAZE: [&str; 5] = [" ##   ", "#D.## ", "#.#..#", "#.O.# ", " ###  "];
+
+    /// IntCode droid moving in the maze, `#` and spaces being walls
+    fn droid(maze: &[&str]) -> Vec<i64> {
+        let width = maze.iter().map(|r| r.len()).max().unwrap_or(0) as i64;
+        let cells: Vec<i64> = maze
+            .iter()
+            .flat_map(|row| {
+                let row = format!("{:<w$}", row, w = width as usize);
+                row.chars()
+                    .map(|c| match c {
+                        '.' | 'D' => 1,
+                        'O' => 2,
+                        _ => 0,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let start = maze
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| Some(y as i64 * width + row.find('D')? as i64))
+            .unwrap();
+        // Data after the code: command, next position, cell, position, the
+        // moves of each command and the maze
+        let (cmd, new, cell, pos, moves, map) = (35, 36, 37, 38, 39, 43);
+        let mut program = vec![
+            3,
+            cmd, // read the command
+            1001,
+            cmd,
+            moves - 1,
+            7, // address of its move
+            1,
+            0,
+            pos,
+            new, // next position
+            1001,
+            new,
+            map,
+            15, // address of its cell
+            1001,
+            0,
+            0,
+            cell, // cell at the next position
+            1005,
+            cell,
+            26, // move unless a wall
+            104,
+            0,
+            1105,
+            1,
+            0, // report the wall
+            1001,
+            new,
+            0,
+            pos, // move
+            4,
+            cell,
+            1105,
+            1,
+            0, // report the cell
+        ];
+        program.extend_from_slice(&[0, 0, 0, start, -width, width, -1, 1]);
+        program.extend(cells);
+        program
+    }
+
+    #[test]
+    fn test_droid() {
+        let program = droid(&MAZE);
+        let map = explore(&program).unwrap();
+        assert_eq!(render(&map), MAZE.to_vec());
+        assert_eq!(part1(&program), Ok(3));
+        assert_eq!(part2(&program), Ok(4));
+
+        let sealed = droid(&["###", "#D#", "###"]);
+        assert_eq!(
+            render(&explore(&sealed).unwrap()),
+            vec![" # ", "#D#", " # "]
+        );
+        assert!(part1(&sealed).is_err());
+    }
+}
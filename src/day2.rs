@@ -1,51 +1,110 @@
+use super::error::InputError;
+use super::intcode::parse_program;
+use super::symbolic::{Engine, Goal};
+
 #[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<u32> {
-    input
-        .split(',')
-        .map(|x| x.parse::<u32>().unwrap())
-        .collect()
+pub fn input_generator(input: &str) -> Result<Vec<i64>, InputError> {
+    parse_program(input).map_err(|e| InputError::program(2, e))
 }
 
-fn intcode_compute(mut input: Vec<u32>) -> Vec<u32> {
+fn intcode_compute(mut input: Vec<i64>) -> Result<Vec<i64>, InputError> {
     let mut index = 0;
-    while input[index] != 99 {
+    let read = |input: &[i64], i: i64| {
+        let value = if i < 0 { None } else { input.get(i as usize) };
+        value
+            .copied()
+            .ok_or_else(|| InputError::new(2, format!("address {} out of the program", i)))
+    };
+    loop {
+        let operation: fn(i64, i64) -> Option<i64> = match read(&input, index)? {
+            1 => i64::checked_add,
+            2 => i64::checked_mul,
+            99 => break,
+            code => {
+                return Err(InputError::new(
+                    2,
+                    format!("unexpected opcode {} at address {}", code, index),
+                ))
+            }
+        };
         let (a, b, r) = (
-            input[index + 1] as usize,
-            input[index + 2] as usize,
-            input[index + 3] as usize,
+            read(&input, index + 1)?,
+            read(&input, index + 2)?,
+            read(&input, index + 3)?,
         );
-        match input[index] {
-            1 => input[r] = input[a] + input[b],
-            2 => input[r] = input[a] * input[b],
-            _ => panic!("Unexpected input value {}", input[0]),
-        }
+        let value = operation(read(&input, a)?, read(&input, b)?)
+            .ok_or_else(|| InputError::new(2, format!("overflow at address {}", index)))?;
+        read(&input, r)?;
+        input[r as usize] = value;
         index += 4;
     }
-    input
+    Ok(input)
 }
 
 #[aoc(day2, part1)]
-pub fn part1(input: &[u32]) -> String {
-    let res = intcode_compute(input.to_vec());
+pub fn part1(input: &[i64]) -> Result<String, InputError> {
+    let res = intcode_compute(input.to_vec())?;
     let res: Vec<String> = res.iter().map(|x| x.to_string()).collect();
-    res.join(",")
+    Ok(res.join(","))
+}
+
+fn no_solution() -> InputError {
+    InputError::new(2, "no noun and verb produce 19690720")
+}
+
+/// The noun and verb are stored at addresses 1 and 2
+fn check_length(input: &[i64]) -> Result<(), InputError> {
+    if input.len() < 3 {
+        return Err(InputError::new(
+            2,
+            "program too short for a noun and a verb",
+        ));
+    }
+    Ok(())
 }
 
 #[aoc(day2, part2)]
-pub fn part2(input: &[u32]) -> u32 {
+pub fn part2(input: &[i64]) -> Result<i64, InputError> {
+    check_length(input)?;
+    let mut engine = Engine::new(input);
+    let noun = engine.symbol_at(1, 0..100);
+    let verb = engine.symbol_at(2, 0..100);
+    let goal = Goal::Halt {
+        address: 0,
+        value: 19_690_720,
+    };
+    match engine.solve(&goal) {
+        Ok(Some(model)) => Ok(100 * model.value(noun) + model.value(verb)),
+        Ok(None) => Err(no_solution()),
+        Err(e) => Err(InputError::new(
+            2,
+            format!("cannot solve the program symbolically: {:?}", e),
+        )),
+    }
+}
+
+#[aoc(day2, part2, BruteForce)]
+pub fn part2_brute_force(input: &[i64]) -> Result<i64, InputError> {
+    check_length(input)?;
     let input = input.to_vec();
+    // A failing run is not a solution, like an overflowing path of the
+    // symbolic search, its error being kept in case none is found
+    let mut failure = None;
     for noun in 0..100 {
         for verb in 0..100 {
             let mut fresh_input = input.clone();
             fresh_input[1] = noun;
             fresh_input[2] = verb;
-            let res = intcode_compute(fresh_input);
-            if res[0] == 19_690_720 {
-                return 100 * noun + verb;
+            match intcode_compute(fresh_input) {
+                Ok(res) if res[0] == 19_690_720 => return Ok(100 * noun + verb),
+                Ok(_) => {}
+                Err(e) => {
+                    failure.get_or_insert(e);
+                }
             }
         }
     }
-    0
+    Err(failure.unwrap_or_else(no_solution))
 }
 
 #[cfg(test)]
@@ -54,12 +113,42 @@ pub mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator("1,0,0,0,99")), "2,0,0,0,99");
-        assert_eq!(part1(&input_generator("2,3,0,3,99")), "2,3,0,6,99");
-        assert_eq!(part1(&input_generator("2,4,4,5,99,0")), "2,4,4,5,99,9801");
         assert_eq!(
-            part1(&input_generator("1,1,1,4,99,5,6,0,99")),
+            part1(&input_generator("1,0,0,0,99").unwrap()).unwrap(),
+            "2,0,0,0,99"
+        );
+        assert_eq!(
+            part1(&input_generator("2,3,0,3,99").unwrap()).unwrap(),
+            "2,3,0,6,99"
+        );
+        assert_eq!(
+            part1(&input_generator("2,4,4,5,99,0").unwrap()).unwrap(),
+            "2,4,4,5,99,9801"
+        );
+        assert_eq!(
+            part1(&input_generator("1,1,1,4,99,5,6,0,99").unwrap()).unwrap(),
             "30,1,1,4,2,5,6,0,99"
         );
+        let e = part1(&input_generator("1,0,0,0,42").unwrap()).unwrap_err();
+        assert_eq!(e.to_string(), "day 2: unexpected opcode 42 at address 4");
+        assert!(part1(&input_generator("1,0,0,9,99").unwrap()).is_err());
+        let e = part1(&input_generator("1,-1,0,0,99").unwrap()).unwrap_err();
+        assert_eq!(e.to_string(), "day 2: address -1 out of the program");
+        let e = part1(&input_generator("2,5,5,0,99,9223372036854775807").unwrap()).unwrap_err();
+        assert_eq!(e.to_string(), "day 2: overflow at address 0");
+    }
+
+    #[test]
+    fn test_part2_overflow() {
+        // The cells at 5 and 6 only give 19690720 by wrapping around
+        let input = input_generator("2,0,0,0,99,3,-6148914691229953632,4,4922680").unwrap();
+        assert_eq!(part2(&input).unwrap(), 708);
+        assert_eq!(part2_brute_force(&input).unwrap(), 708);
+        let input = input_generator("2,0,0,0,99,3,-6148914691229953632").unwrap();
+        assert_eq!(
+            part2(&input).unwrap_err().to_string(),
+            "day 2: no noun and verb produce 19690720"
+        );
+        assert!(part2_brute_force(&input).is_err());
     }
 }
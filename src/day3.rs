@@ -1,9 +1,37 @@
+use super::error::InputError;
+
 #[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> Vec<Vec<String>> {
-    input
-        .lines()
-        .map(|l| l.split(',').map(|x| x.to_string()).collect())
-        .collect()
+pub fn input_generator(input: &str) -> Result<Vec<Vec<(char, u32)>>, InputError> {
+    let mut wires = vec![];
+    for (l, line) in input.lines().enumerate() {
+        let mut wire = vec![];
+        let mut column = 1;
+        for token in line.split(',') {
+            let mut chars = token.chars();
+            match chars.next() {
+                Some(d @ 'R') | Some(d @ 'L') | Some(d @ 'U') | Some(d @ 'D') => {
+                    wire.push((d, InputError::parse(3, l + 1, column + 1, chars.as_str())?))
+                }
+                _ => {
+                    return Err(InputError::at(
+                        3,
+                        l + 1,
+                        column,
+                        format!("invalid direction {:?}", token),
+                    ))
+                }
+            }
+            column += token.chars().count() + 1;
+        }
+        wires.push(wire);
+    }
+    if wires.len() != 2 {
+        return Err(InputError::new(
+            3,
+            format!("expected 2 wires, got {}", wires.len()),
+        ));
+    }
+    Ok(wires)
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -26,7 +54,7 @@ struct Wire {
 }
 
 impl Wire {
-    fn new(directions: Vec<String>) -> Self {
+    fn new(directions: &[(char, u32)]) -> Self {
         let mut n = Wire {
             pos: Position {
                 x: 0,
@@ -39,47 +67,46 @@ impl Wire {
         n
     }
 
-    fn execute_direction(&mut self, direction: &str) {
-        let (d, steps) = (&direction[0..1], direction[1..].parse::<u32>().unwrap());
+    fn execute_direction(&mut self, (d, steps): (char, u32)) {
         let mut new_pos: Vec<Position> = match d {
-            "R" => (0..=steps)
+            'R' => (0..=steps)
                 .map(|s| Position {
                     x: self.pos.x + s as i64,
                     y: self.pos.y,
                     steps: self.pos.steps + u64::from(s),
                 })
                 .collect(),
-            "L" => (0..=steps)
+            'L' => (0..=steps)
                 .map(|s| Position {
                     x: self.pos.x - s as i64,
                     y: self.pos.y,
                     steps: self.pos.steps + u64::from(s),
                 })
                 .collect(),
-            "U" => (0..=steps)
+            'U' => (0..=steps)
                 .map(|s| Position {
                     x: self.pos.x,
                     y: self.pos.y + s as i64,
                     steps: self.pos.steps + u64::from(s),
                 })
                 .collect(),
-            "D" => (0..=steps)
+            'D' => (0..=steps)
                 .map(|s| Position {
                     x: self.pos.x,
                     y: self.pos.y - s as i64,
                     steps: self.pos.steps + u64::from(s),
                 })
                 .collect(),
-            _ => panic!("Unknown direction value {}", d),
+            _ => unreachable!(),
         };
         // println!("PRV POS {:?} DIR {} NEW POS {:?}", self.pos, direction, new_pos.last().unwrap().clone());
         self.pos = *new_pos.last().unwrap();
         self.positions.append(&mut new_pos)
     }
 
-    fn execute_directions(&mut self, directions: Vec<String>) {
-        for d in directions {
-            self.execute_direction(&d);
+    fn execute_directions(&mut self, directions: &[(char, u32)]) {
+        for &d in directions {
+            self.execute_direction(d);
         }
     }
 }
@@ -96,36 +123,38 @@ fn get_same_coordinates(wire1: Wire, wire2: Wire) -> Vec<(Position, Position)> {
     res
 }
 
-fn get_smallest_distances(coordinates: Vec<(Position, Position)>) -> u32 {
-    println!("SMALLES FIND {:?}", coordinates);
+fn no_crossing() -> InputError {
+    InputError::new(3, "the wires never cross")
+}
+
+fn get_smallest_distances(coordinates: Vec<(Position, Position)>) -> Result<u32, InputError> {
     coordinates
         .iter()
-        .map(|(p1, _p2)| p1.x.abs() + p1.y.abs())
+        .map(|(p1, _p2)| (p1.x.abs() + p1.y.abs()) as u32)
         .min()
-        .unwrap() as u32
+        .ok_or_else(no_crossing)
 }
 
-fn get_smallest_steps(coordinates: Vec<(Position, Position)>) -> u32 {
-    println!("SMALLES FIND {:?}", coordinates);
+fn get_smallest_steps(coordinates: Vec<(Position, Position)>) -> Result<u32, InputError> {
     coordinates
         .iter()
-        .map(|(p1, p2)| p1.steps + p2.steps)
+        .map(|(p1, p2)| (p1.steps + p2.steps) as u32)
         .min()
-        .unwrap() as u32
+        .ok_or_else(no_crossing)
 }
 
 #[aoc(day3, part1)]
-pub fn part1(input: &[Vec<String>]) -> u32 {
-    let wire1 = Wire::new(input[0].clone());
-    let wire2 = Wire::new(input[1].clone());
+pub fn part1(input: &[Vec<(char, u32)>]) -> Result<u32, InputError> {
+    let wire1 = Wire::new(&input[0]);
+    let wire2 = Wire::new(&input[1]);
     let same_coord = get_same_coordinates(wire1, wire2);
     get_smallest_distances(same_coord)
 }
 
 #[aoc(day3, part2)]
-pub fn part2(input: &[Vec<String>]) -> u32 {
-    let wire1 = Wire::new(input[0].clone());
-    let wire2 = Wire::new(input[1].clone());
+pub fn part2(input: &[Vec<(char, u32)>]) -> Result<u32, InputError> {
+    let wire1 = Wire::new(&input[0]);
+    let wire2 = Wire::new(&input[1]);
     let same_coord = get_same_coordinates(wire1, wire2);
     get_smallest_steps(same_coord)
 }
@@ -136,37 +165,71 @@ pub mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator("R8,U5,L5,D3\nU7,R6,D4,L4")), 6);
-        assert_eq!(part1(&input_generator("R8,D5,L5,U3\nD7,R6,U4,L4")), 6);
         assert_eq!(
-            part1(&input_generator(
-                "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83"
-            )),
-            159
+            part1(&input_generator("R8,U5,L5,D3\nU7,R6,D4,L4").unwrap()),
+            Ok(6)
+        );
+        assert_eq!(
+            part1(&input_generator("R8,D5,L5,U3\nD7,R6,U4,L4").unwrap()),
+            Ok(6)
+        );
+        assert_eq!(
+            part1(
+                &input_generator(
+                    "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83"
+                )
+                .unwrap()
+            ),
+            Ok(159)
         );
         assert_eq!(
             part1(&input_generator(
                 "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
-            )),
-            135
+            ).unwrap()),
+            Ok(135)
         );
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&input_generator("R8,U5,L5,D3\nU7,R6,D4,L4")), 30);
-        assert_eq!(part2(&input_generator("R8,D5,L5,U3\nD7,R6,U4,L4")), 30);
         assert_eq!(
-            part2(&input_generator(
-                "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83"
-            )),
-            610
+            part2(&input_generator("R8,U5,L5,D3\nU7,R6,D4,L4").unwrap()),
+            Ok(30)
+        );
+        assert_eq!(
+            part2(&input_generator("R8,D5,L5,U3\nD7,R6,U4,L4").unwrap()),
+            Ok(30)
+        );
+        assert_eq!(
+            part2(
+                &input_generator(
+                    "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83"
+                )
+                .unwrap()
+            ),
+            Ok(610)
         );
         assert_eq!(
             part2(&input_generator(
                 "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7"
-            )),
-            410
+            ).unwrap()),
+            Ok(410)
+        );
+    }
+
+    #[test]
+    fn test_input_generator() {
+        let e = input_generator("R8,U5\nU7,X6").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 3, line 2, column 4: invalid direction \"X6\""
+        );
+        let e = input_generator("R8,U5\nU7,R6a").unwrap_err();
+        assert_eq!(e.location.map(|l| l.column), Some(5));
+        assert!(input_generator("R8,U5").is_err());
+        assert_eq!(
+            part1(&input_generator("R8\nU5").unwrap()),
+            Err(no_crossing())
         );
     }
 }
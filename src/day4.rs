@@ -1,3 +1,5 @@
+use super::error::InputError;
+
 fn number_to_vec(n: u32) -> Vec<u32> {
     let mut digits = Vec::new();
     let mut n = n;
@@ -54,14 +56,22 @@ fn check_password_strict(n: u32) -> bool {
     check_digit_increase(&vec_n) && check_digit_double_strict(&vec_n)
 }
 
+/// The puzzle input is the range of passwords, e.g. `123456-654321`
+#[aoc_generator(day4)]
+pub fn input_generator(input: &str) -> Result<(u32, u32), InputError> {
+    let input = input.trim();
+    let dash = input
+        .find('-')
+        .ok_or_else(|| InputError::at(4, 1, 1, format!("expected a range, got {:?}", input)))?;
+    let start = InputError::parse(4, 1, 1, &input[..dash])?;
+    let end = InputError::parse(4, 1, dash + 2, &input[dash + 1..])?;
+    Ok((start, end))
+}
+
 #[aoc(day4, part1)]
-pub fn part1(range: &str) -> usize {
-    let range: Vec<u32> = range
-        .split('-')
-        .map(|x| x.parse::<u32>().unwrap())
-        .collect();
+pub fn part1(range: &(u32, u32)) -> usize {
     let mut passwords = vec![];
-    for n in range[0]..=range[1] {
+    for n in range.0..=range.1 {
         if check_password(n) {
             passwords.push(n)
         }
@@ -70,13 +80,9 @@ pub fn part1(range: &str) -> usize {
 }
 
 #[aoc(day4, part2)]
-pub fn part2(range: &str) -> usize {
-    let range: Vec<u32> = range
-        .split('-')
-        .map(|x| x.parse::<u32>().unwrap())
-        .collect();
+pub fn part2(range: &(u32, u32)) -> usize {
     let mut passwords = vec![];
-    for n in range[0]..=range[1] {
+    for n in range.0..=range.1 {
         if check_password_strict(n) {
             passwords.push(n)
         }
@@ -90,15 +96,23 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(check_password(11_1111), true);
-        assert_eq!(check_password(22_3450), false);
-        assert_eq!(check_password(12_3789), false);
+        assert!(check_password(11_1111));
+        assert!(!check_password(22_3450));
+        assert!(!check_password(12_3789));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(check_password_strict(11_2233), true);
-        assert_eq!(check_password_strict(12_3444), false);
-        assert_eq!(check_password_strict(11_1122), true);
+        assert!(check_password_strict(11_2233));
+        assert!(!check_password_strict(12_3444));
+        assert!(check_password_strict(11_1122));
+    }
+
+    #[test]
+    fn test_input_generator() {
+        assert_eq!(input_generator("111111-111123\n"), Ok((111_111, 111_123)));
+        let e = input_generator("111111-11x").unwrap_err();
+        assert_eq!(e.location.map(|l| l.column), Some(8));
+        assert!(input_generator("111111").is_err());
     }
 }
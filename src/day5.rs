@@ -1,57 +1,123 @@
-use super::intcode::IntCode;
+use super::error::InputError;
+use super::intcode::{parse_program, ExitCode, InstructionSet, IntCode};
+use std::fmt;
 
 #[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Vec<i64> {
-    input
-        .split(',')
-        .map(|x| x.parse::<i64>().unwrap())
-        .collect()
+pub fn input_generator(input: &str) -> Result<Vec<i64>, InputError> {
+    parse_program(input).map_err(|e| InputError::program(5, e))
+}
+
+#[derive(Debug)]
+enum DiagnosticError {
+    /// The test at this output index reported a non-zero value
+    TestFailure {
+        output: usize,
+        value: i64,
+    },
+    NoOutput,
+    /// The program did not run to its end
+    Aborted(ExitCode),
+}
+
+impl fmt::Display for DiagnosticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticError::TestFailure { output, value } => {
+                write!(f, "test failure at output {}: {}", output, value)
+            }
+            DiagnosticError::NoOutput => write!(f, "no diagnostic code"),
+            DiagnosticError::Aborted(code) => write!(f, "program aborted with {:?}", code),
+        }
+    }
+}
+
+/// Run the TEST program for a system, all outputs but the last one being
+/// test results which must be zero, the last one the diagnostic code.
+fn run_diagnostic(
+    input: &[i64],
+    system_id: i64,
+    instructions: InstructionSet,
+) -> Result<i64, DiagnosticError> {
+    let mut i = IntCode::with_instructions(input.to_vec(), vec![system_id], instructions);
+    loop {
+        match i.execute() {
+            ExitCode::Output(_) => {}
+            ExitCode::Stop => break,
+            code => return Err(DiagnosticError::Aborted(code)),
+        }
+    }
+    let (code, tests) = i.output.split_last().ok_or(DiagnosticError::NoOutput)?;
+    if let Some((output, &value)) = tests.iter().enumerate().find(|(_, &v)| v != 0) {
+        return Err(DiagnosticError::TestFailure { output, value });
+    }
+    Ok(*code)
 }
 
 #[aoc(day5, part1)]
-pub fn part1(input: &[i64]) -> String {
-    let mut i = IntCode::new(input.to_vec(), vec![]);
-    i.execute();
-    let res = i.content;
-    let res: Vec<String> = res.iter().map(|x| x.to_string()).collect();
-    res.join(",")
+pub fn part1(input: &[i64]) -> Result<i64, InputError> {
+    // The air conditioner unit only needs the first extension of the computer
+    run_diagnostic(input, 1, InstructionSet::day5_part1())
+        .map_err(|e| InputError::new(5, e.to_string()))
 }
 
 #[aoc(day5, part2)]
-pub fn part2(input: &[i64]) -> String {
-    let mut i = IntCode::new(input.to_vec(), vec![]);
-    i.execute();
-    let res = i.content;
-    let res: Vec<String> = res.iter().map(|x| x.to_string()).collect();
-    res.join(",")
+pub fn part2(input: &[i64]) -> Result<i64, InputError> {
+    run_diagnostic(input, 5, InstructionSet::day5()).map_err(|e| InputError::new(5, e.to_string()))
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
 
+    fn memory(input: &str) -> Vec<i64> {
+        let mut i = IntCode::new(input_generator(input).unwrap(), vec![]);
+        i.execute();
+        i.content
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&input_generator("1,0,0,0,99")), "2,0,0,0,99");
-        assert_eq!(part1(&input_generator("2,3,0,3,99")), "2,3,0,6,99");
-        assert_eq!(part1(&input_generator("2,4,4,5,99,0")), "2,4,4,5,99,9801");
+        assert_eq!(memory("1,0,0,0,99"), vec![2, 0, 0, 0, 99]);
+        assert_eq!(memory("2,3,0,3,99"), vec![2, 3, 0, 6, 99]);
+        assert_eq!(memory("2,4,4,5,99,0"), vec![2, 4, 4, 5, 99, 9801]);
         assert_eq!(
-            part1(&input_generator("1,1,1,4,99,5,6,0,99")),
-            "30,1,1,4,2,5,6,0,99"
+            memory("1,1,1,4,99,5,6,0,99"),
+            vec![30, 1, 1, 4, 2, 5, 6, 0, 99]
         );
     }
 
     #[test]
     fn test_part1_parameters() {
-        assert_eq!(part1(&input_generator("1002,4,3,4,33")), "1002,4,3,4,99");
+        assert_eq!(memory("1002,4,3,4,33"), vec![1002, 4, 3, 4, 99]);
+        assert_eq!(memory("1101,100,-1,4,0"), vec![1101, 100, -1, 4, 99]);
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        // Two tests then the system ID
+        let input = input_generator("3,0,104,0,104,0,4,0,99").unwrap();
+        assert_eq!(part1(&input), Ok(1));
+        assert_eq!(part2(&input), Ok(5));
+        let input = input_generator("3,0,104,0,104,3,4,0,99").unwrap();
+        assert_eq!(
+            part1(&input).unwrap_err().to_string(),
+            "day 5: test failure at output 1: 3"
+        );
+        let input = input_generator("3,0,99").unwrap();
+        assert_eq!(
+            part1(&input).unwrap_err().to_string(),
+            "day 5: no diagnostic code"
+        );
+        // Jumps are not available to the air conditioner
+        let input = input_generator("3,0,1105,1,5,104,0,99").unwrap();
         assert_eq!(
-            part1(&input_generator("1101,100,-1,4,0")),
-            "1101,100,-1,4,99"
+            part1(&input).unwrap_err().to_string(),
+            "day 5: program aborted with UnknowCode"
         );
     }
 
     fn prepare_test(input: &str, manual_input: Vec<i64>) -> Vec<i64> {
-        let input = &input_generator(input);
+        let input = &input_generator(input).unwrap();
         let mut i = IntCode::new(input.to_vec(), manual_input);
         i.execute();
         i.output
@@ -1,34 +1,59 @@
+use super::error::InputError;
 use std::{
     collections::{HashMap, HashSet},
     str::FromStr,
 };
 
+#[derive(Debug)]
 pub struct Orbit {
     parent: String,
     child: String,
 }
 
 impl FromStr for Orbit {
-    type Err = ();
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let data: Vec<&str> = s.trim().split(')').collect();
-        Ok(Orbit {
-            parent: data[0].into(),
-            child: data[1].into(),
-        })
+        match s.trim().split_once(')') {
+            Some((parent, child)) if !parent.is_empty() && !child.is_empty() => Ok(Orbit {
+                parent: parent.into(),
+                child: child.into(),
+            }),
+            _ => Err(format!("expected an orbit like \"A)B\", got {:?}", s)),
+        }
     }
 }
 
 #[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> Vec<Orbit> {
-    input.lines().map(|l| l.parse().unwrap()).collect()
+pub fn input_generator(input: &str) -> Result<Vec<Orbit>, InputError> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(l, s)| s.parse().map_err(|e| InputError::at(6, l + 1, 1, e)))
+        .collect()
 }
 
-fn steps(g: &HashMap<String, Vec<String>>, node: &str, rec: usize) -> usize {
+fn steps<'a>(
+    g: &'a HashMap<String, Vec<String>>,
+    node: &'a str,
+    rec: usize,
+    visited: &mut HashSet<&'a str>,
+) -> Result<usize, InputError> {
+    if !visited.insert(node) {
+        return Err(InputError::new(
+            6,
+            format!("{} orbits more than one object or is in a loop", node),
+        ));
+    }
     match g.get(node) {
-        Some(v) => v.len() * rec + v.iter().map(|k| steps(g, k, rec + 1)).sum::<usize>(),
-        None => 0,
+        Some(v) => {
+            let mut total = v.len() * rec;
+            for k in v {
+                total += steps(g, k, rec + 1, visited)?;
+            }
+            Ok(total)
+        }
+        None => Ok(0),
     }
 }
 
@@ -70,7 +95,7 @@ fn find_path_len<'a>(
 }
 
 #[aoc(day6, part1)]
-pub fn part1(input: &[Orbit]) -> usize {
+pub fn part1(input: &[Orbit]) -> Result<usize, InputError> {
     let g: HashMap<String, Vec<String>> = input
         .iter()
         .map(|p1| {
@@ -83,13 +108,24 @@ pub fn part1(input: &[Orbit]) -> usize {
         })
         .collect();
 
-    steps(&g, "COM", 1)
-    // println!("Final result {}", r);
+    let mut visited = HashSet::new();
+    let total = steps(&g, "COM", 1, &mut visited)?;
+    match input.iter().find(|o| !visited.contains(o.child.as_str())) {
+        Some(o) => Err(InputError::new(
+            6,
+            format!("{} does not orbit COM", o.child),
+        )),
+        None => Ok(total),
+    }
 }
 
 #[aoc(day6, part2)]
-pub fn part2(input: &[Orbit]) -> usize {
-    find_path_len(input, "YOU", "SAN", &mut HashSet::new()).unwrap() - 2
+pub fn part2(input: &[Orbit]) -> Result<usize, InputError> {
+    let len = find_path_len(input, "YOU", "SAN", &mut HashSet::new())
+        .ok_or_else(|| InputError::new(6, "no path from YOU to SAN"))?;
+    // Transfers between the objects YOU and SAN orbit
+    len.checked_sub(2)
+        .ok_or_else(|| InputError::new(6, "YOU and SAN orbit one another"))
 }
 
 #[cfg(test)]
@@ -99,8 +135,9 @@ pub mod tests {
     #[test]
     fn test_part1() {
         assert_eq!(
-            part1(&input_generator(
-                "B)G
+            part1(
+                &input_generator(
+                    "B)G
 G)H
 D)I
 E)J
@@ -111,16 +148,19 @@ C)D
 D)E
 E)F
 K)L"
-            )),
-            42
+                )
+                .unwrap()
+            ),
+            Ok(42)
         );
     }
 
     #[test]
     fn test_part2() {
         assert_eq!(
-            part2(&input_generator(
-                "COM)B
+            part2(
+                &input_generator(
+                    "COM)B
 B)C
 C)D
 D)E
@@ -133,8 +173,39 @@ J)K
 K)L
 K)YOU
 I)SAN"
-            )),
-            4
+                )
+                .unwrap()
+            ),
+            Ok(4)
+        );
+    }
+
+    #[test]
+    fn test_input_generator() {
+        let e = input_generator("COM)B\nB-C").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 6, line 2, column 1: expected an orbit like \"A)B\", got \"B-C\""
+        );
+        assert!(input_generator("COM)").is_err());
+        let input = input_generator("COM)B\nB)YOU").unwrap();
+        assert!(part2(&input).is_err());
+        let input = input_generator("COM)YOU\nCOM)B\nB)SAN").unwrap();
+        assert_eq!(part2(&input), Ok(1));
+        let input = input_generator("COM)YOU\nYOU)SAN").unwrap();
+        assert_eq!(
+            part2(&input).unwrap_err().to_string(),
+            "day 6: YOU and SAN orbit one another"
+        );
+        let input = input_generator("COM)A\nA)B\nB)A").unwrap();
+        assert_eq!(
+            part1(&input).unwrap_err().to_string(),
+            "day 6: A orbits more than one object or is in a loop"
+        );
+        let input = input_generator("COM)A\nB)C\nC)B").unwrap();
+        assert_eq!(
+            part1(&input).unwrap_err().to_string(),
+            "day 6: C does not orbit COM"
         );
     }
 }
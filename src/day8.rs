@@ -1,43 +1,54 @@
-use itertools::Itertools;
+use super::error::InputError;
+use super::registry::Answer;
+use super::sif::{Image, SifError};
 
-#[aoc_generator(day8)]
-pub fn input_generator(input: &str) -> Vec<u32> {
-    input.chars().map(|x| x.to_digit(10).unwrap()).collect()
-}
+const WIDTH: usize = 25;
+const HEIGHT: usize = 6;
 
-fn count_digit(x: &[u32], digit: u32) -> usize {
-    x.iter().filter(|&&x| x == digit).count()
+#[aoc_generator(day8)]
+pub fn input_generator(input: &str) -> Result<Image, InputError> {
+    Image::parse(input, WIDTH, HEIGHT).map_err(|e| match e {
+        SifError::Pixel {
+            line,
+            column,
+            found,
+        } => InputError::at(8, line, column, format!("invalid pixel {:?}", found)),
+        e => InputError::new(8, e.to_string()),
+    })
 }
 
 #[aoc(day8, part1)]
-fn part1(input: &[u32]) -> usize {
-    let min_zero_chunk = input
-        .chunks(25 * 6)
-        .min_by_key(|x| count_digit(x, 0))
-        .unwrap();
-    let ones = count_digit(min_zero_chunk, 1);
-    let twos = count_digit(min_zero_chunk, 2);
-    ones * twos
+pub fn part1(input: &Image) -> usize {
+    input.checksum()
 }
 
 #[aoc(day8, part2)]
-fn part2(input: &[u32]) -> u32 {
-    let mut res = vec![2; 150];
-    for c in input.chunks(25 * 6) {
-        res = c
-            .iter()
-            .zip(res.iter())
-            .map(|(&p_x, &p_res)| if p_res == 2 { p_x } else { p_res })
-            .collect();
-    }
-    let message = res
-        .iter()
-        .map(|&x| if x == 0 { ' ' } else { '█' })
-        .chunks(25);
+pub fn part2(input: &Image) -> Answer {
+    Answer::Image(input.decode())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
 
-    for l in &message {
-        println!("{}", l.collect::<String>())
+    #[test]
+    fn test_input_generator() {
+        let layer = "0".repeat(150);
+        assert_eq!(input_generator(&layer).unwrap().layers().len(), 1);
+        let e = input_generator(&format!("{}x{}", &layer[..10], &layer[11..])).unwrap_err();
+        assert_eq!(e.to_string(), "day 8, line 1, column 11: invalid pixel 'x'");
+        assert!(input_generator(&layer[1..]).is_err());
     }
 
-    0
+    #[test]
+    fn test_part2() {
+        // A transparent layer, then a lit diagonal over a dark layer
+        let mut input = "2".repeat(150);
+        input.extend((0..150).map(|i| if i % 26 == 0 { '1' } else { '2' }));
+        input.push_str(&"0".repeat(150));
+        let image = input_generator(&input).unwrap().decode();
+        assert_eq!((image.width(), image.height()), (25, 6));
+        assert!((0..6).all(|i| image.lit(i, i)));
+        assert!(!image.lit(1, 0) && !image.lit(24, 5));
+    }
 }
@@ -1,33 +1,44 @@
-use super::intcode::{ExitCode, IntCode};
+use super::error::InputError;
+use super::intcode::{parse_program, ExitCode, IntCode};
 
 #[aoc_generator(day9)]
-pub fn input_generator(input: &str) -> Vec<i64> {
-    input
-        .split(',')
-        .map(|x| x.parse::<i64>().unwrap())
-        .collect()
+pub fn input_generator(input: &str) -> Result<Vec<i64>, InputError> {
+    parse_program(input).map_err(|e| InputError::program(9, e))
 }
 
-#[aoc(day9, part1)]
-fn part1(input: &[i64]) -> i64 {
-    let mut i = IntCode::new(input.to_vec(), vec![1]);
+/// Run BOOST in the given mode, it outputs a single keycode
+fn boost(input: &[i64], mode: i64) -> Result<i64, InputError> {
+    let mut i = IntCode::new(input.to_vec(), vec![mode]);
     loop {
-        if i.execute() == ExitCode::Stop {
-            break;
+        match i.execute() {
+            ExitCode::Output(_) => {}
+            ExitCode::Stop => break,
+            code => {
+                return Err(InputError::new(
+                    9,
+                    format!("BOOST program aborted with {:?}", code),
+                ))
+            }
         }
     }
-    i.output[0]
+    match i.output[..] {
+        [keycode] => Ok(keycode),
+        // Opcodes which do not work properly
+        _ => Err(InputError::new(
+            9,
+            format!("BOOST reported malfunctioning opcodes {:?}", i.output),
+        )),
+    }
+}
+
+#[aoc(day9, part1)]
+pub fn part1(input: &[i64]) -> Result<i64, InputError> {
+    boost(input, 1)
 }
 
 #[aoc(day9, part2)]
-fn part2(input: &[i64]) -> i64 {
-    let mut i = IntCode::new(input.to_vec(), vec![2]);
-    loop {
-        if i.execute() == ExitCode::Stop {
-            break;
-        }
-    }
-    i.output[0]
+pub fn part2(input: &[i64]) -> Result<i64, InputError> {
+    boost(input, 2)
 }
 
 #[cfg(test)]
@@ -35,7 +46,7 @@ pub mod tests {
     use super::*;
 
     fn prepare_test(input: &str, manual_input: Vec<i64>) -> Vec<i64> {
-        let input = &input_generator(input);
+        let input = &input_generator(input).unwrap();
         let mut i = IntCode::new(input.to_vec(), manual_input);
         loop {
             if i.execute() == ExitCode::Stop {
@@ -0,0 +1,99 @@
+//! Error shared by every solution when the puzzle input is malformed or has
+//! no answer.
+
+use super::intcode::ProgramError;
+use std::{error::Error, fmt, str::FromStr};
+
+/// 1-based position of the faulty token in the input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct InputError {
+    pub day: u32,
+    /// `None` when the input as a whole is at fault, e.g. it has no solution
+    pub location: Option<Location>,
+    pub message: String,
+}
+
+impl InputError {
+    pub fn new<S: Into<String>>(day: u32, message: S) -> Self {
+        InputError {
+            day,
+            location: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at<S: Into<String>>(day: u32, line: usize, column: usize, message: S) -> Self {
+        InputError {
+            day,
+            location: Some(Location { line, column }),
+            message: message.into(),
+        }
+    }
+
+    /// Parse a token found at the given line and column
+    pub fn parse<T: FromStr>(
+        day: u32,
+        line: usize,
+        column: usize,
+        token: &str,
+    ) -> Result<T, InputError> {
+        token
+            .parse()
+            .map_err(|_| InputError::at(day, line, column, format!("invalid number {:?}", token)))
+    }
+
+    pub fn program(day: u32, e: ProgramError) -> Self {
+        match e {
+            ProgramError::Invalid {
+                line,
+                column,
+                token,
+            } => InputError::at(day, line, column, format!("invalid value {:?}", token)),
+            e => InputError::new(day, e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "day {}", self.day)?;
+        if let Some(Location { line, column }) = self.location {
+            write!(f, ", line {}, column {}", line, column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// aoc-runner prints failures with `{:#?}`, keep it as readable as `Display`
+impl fmt::Debug for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for InputError {}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let e = InputError::parse::<u32>(1, 3, 1, "12a").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "day 1, line 3, column 1: invalid number \"12a\""
+        );
+        assert_eq!(format!("{:#?}", e), e.to_string());
+        let e = InputError::new(6, "no path from YOU to SAN");
+        assert_eq!(e.to_string(), "day 6: no path from YOU to SAN");
+        let e = InputError::program(2, ProgramError::Empty);
+        assert_eq!(e.to_string(), "day 2: empty program");
+    }
+}
@@ -0,0 +1,313 @@
+//! Animated GIF recording of simulations.
+//!
+//! Every frame is a grid whose cells are turned into indices of a shared
+//! palette, each cell being drawn as a square of `scale` pixels.
+
+use super::grid::DenseGrid;
+use super::image::Rgb;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// LZW codes are at most 12 bits long
+const MAX_CODES: u16 = 4096;
+
+#[derive(Debug, Clone)]
+pub struct Animation {
+    palette: Vec<Rgb>,
+    delay: u16,
+    scale: usize,
+    width: usize,
+    height: usize,
+    frames: Vec<Vec<u8>>,
+}
+
+impl Animation {
+    /// At most 256 colours, GIF delays being in hundredths of a second the
+    /// frame rate is rounded to the closest one
+    pub fn new(palette: Vec<Rgb>, fps: u32, scale: usize) -> Self {
+        assert!(
+            !palette.is_empty() && palette.len() <= 256,
+            "a GIF palette has 1 to 256 colours"
+        );
+        let delay = (100.0 / f64::from(fps.max(1))).round().max(1.0) as u16;
+        Animation {
+            palette,
+            delay,
+            scale: scale.max(1),
+            width: 0,
+            height: 0,
+            frames: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Time each frame is shown, in hundredths of a second
+    pub fn delay(&self) -> u16 {
+        self.delay
+    }
+
+    /// Every frame must have the size of the first one, `colour` giving the
+    /// palette index of a cell
+    pub fn push<T, F: Fn(&T) -> u8>(&mut self, grid: &DenseGrid<T>, colour: F) {
+        let (width, height) = (grid.width() * self.scale, grid.height() * self.scale);
+        if self.frames.is_empty() {
+            self.width = width;
+            self.height = height;
+        }
+        assert_eq!(
+            (width, height),
+            (self.width, self.height),
+            "frames of different sizes"
+        );
+        let colours = grid.map(colour);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(colours[(x / self.scale, y / self.scale)]);
+            }
+        }
+        self.frames.push(pixels);
+    }
+
+    /// Bits per palette index, at least 1
+    fn depth(&self) -> u8 {
+        let mut bits = 1;
+        while 1 << bits < self.palette.len() {
+            bits += 1;
+        }
+        bits
+    }
+
+    /// Looping animation
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if self.width > 0xffff || self.height > 0xffff {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frames are too large for a GIF",
+            ));
+        }
+        let (width, height) = (self.width as u16, self.height as u16);
+        let depth = self.depth();
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // Global colour table, background colour and pixel aspect ratio
+        out.write_all(&[0x80 | ((depth - 1) << 4) | (depth - 1), 0, 0])?;
+        for i in 0..1 << depth {
+            out.write_all(self.palette.get(i).unwrap_or(&[0, 0, 0]))?;
+        }
+        // Repeat forever
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\0\0\0")?;
+
+        let min_code_size = depth.max(2);
+        for frame in &self.frames {
+            out.write_all(&[0x21, 0xf9, 4, 0])?;
+            out.write_all(&self.delay.to_le_bytes())?;
+            out.write_all(&[0, 0])?;
+            out.write_all(&[0x2c, 0, 0, 0, 0])?;
+            out.write_all(&width.to_le_bytes())?;
+            out.write_all(&height.to_le_bytes())?;
+            out.write_all(&[0, min_code_size])?;
+            for block in lzw(frame, min_code_size).chunks(255) {
+                out.write_all(&[block.len() as u8])?;
+                out.write_all(block)?;
+            }
+            out.write_all(&[0])?;
+        }
+        out.write_all(&[0x3b])
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut out = vec![];
+        self.write(&mut out)?;
+        fs::write(path, out)
+    }
+}
+
+/// Codes packed from the least significant bit
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u32,
+    len: u8,
+}
+
+impl BitWriter {
+    fn push(&mut self, code: u16, size: u8) {
+        self.current |= u32::from(code) << self.len;
+        self.len += size;
+        while self.len >= 8 {
+            self.bytes.push(self.current as u8);
+            self.current >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.current as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Variable length LZW compression of palette indices, the table being
+/// cleared before its last code is used as giflib does
+fn lzw(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1 << min_code_size;
+    let end = clear + 1;
+    let mut bits = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut size = min_code_size + 1;
+    let mut next = end + 1;
+    bits.push(clear, size);
+    let mut prefix = None;
+    for &index in indices {
+        prefix = Some(match prefix {
+            None => u16::from(index),
+            Some(p) => match table.get(&(p, index)) {
+                Some(&code) => code,
+                None => {
+                    bits.push(p, size);
+                    if next == MAX_CODES - 1 {
+                        bits.push(clear, size);
+                        table.clear();
+                        size = min_code_size + 1;
+                        next = end + 1;
+                    } else {
+                        table.insert((p, index), next);
+                        next += 1;
+                        if next > 1 << size && size < 12 {
+                            size += 1;
+                        }
+                    }
+                    u16::from(index)
+                }
+            },
+        });
+    }
+    if let Some(p) = prefix {
+        bits.push(p, size);
+    }
+    bits.push(end, size);
+    bits.finish()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn unlzw(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1 << min_code_size;
+        let reset = || -> Vec<Vec<u8>> { (0..clear + 2).map(|i| vec![i as u8]).collect() };
+        let mut table = reset();
+        let mut size = min_code_size + 1;
+        let (mut current, mut len) = (0u32, 0);
+        let mut prev: Option<usize> = None;
+        let mut out = vec![];
+        for &byte in data {
+            current |= u32::from(byte) << len;
+            len += 8;
+            while len >= size {
+                let code = (current & ((1 << size) - 1)) as usize;
+                current >>= size;
+                len -= size;
+                if code == clear {
+                    table = reset();
+                    size = min_code_size + 1;
+                    prev = None;
+                    continue;
+                }
+                if code == clear + 1 {
+                    return out;
+                }
+                let entry = match (table.get(code), prev) {
+                    (Some(entry), _) => entry.clone(),
+                    (None, Some(p)) => {
+                        let mut entry = table[p].clone();
+                        entry.push(entry[0]);
+                        entry
+                    }
+                    (None, None) => panic!("unknown first code {}", code),
+                };
+                if let Some(p) = prev {
+                    if table.len() < MAX_CODES as usize {
+                        let mut added = table[p].clone();
+                        added.push(entry[0]);
+                        table.push(added);
+                    }
+                }
+                out.extend_from_slice(&entry);
+                prev = Some(code);
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+        }
+        panic!("missing end code")
+    }
+
+    #[test]
+    fn test_lzw() {
+        assert_eq!(unlzw(&lzw(&[], 2), 2), vec![]);
+        let indices = [1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2];
+        assert_eq!(unlzw(&lzw(&indices, 2), 2), indices);
+        // Enough distinct sequences to fill the table a few times
+        let noise: Vec<u8> = (0..50_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 27) as u8)
+            .collect();
+        assert_eq!(unlzw(&lzw(&noise, 5), 5), noise);
+    }
+
+    #[test]
+    fn test_lzw_reference() {
+        // Sample image of "What's in a GIF", with a code size bump
+        let sample: Vec<u8> = b"1111122222111112222211111222221110000222111000022222200001112220000111222221111122222111112222211111"
+            .iter()
+            .map(|b| b - b'0')
+            .collect();
+        let reference = [
+            0x8c, 0x2d, 0x99, 0x87, 0x2a, 0x1c, 0xdc, 0x33, 0xa0, 0x02, 0x75, 0xec, 0x95, 0xfa,
+            0xa8, 0xde, 0x60, 0x8c, 0x04, 0x91, 0x4c, 0x01,
+        ];
+        assert_eq!(lzw(&sample, 2), reference);
+        assert_eq!(unlzw(&reference, 2), sample);
+        // Encoded by giflib, long enough for its table to be cleared once
+        let mut seed = 1u32;
+        let noise: Vec<u8> = (0..20_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 30) as u8
+            })
+            .collect();
+        let reference = include_bytes!("../tests/data/giflib_noise.lzw");
+        assert_eq!(unlzw(reference, 2), noise);
+        assert_eq!(lzw(&noise, 2), &reference[..]);
+    }
+
+    #[test]
+    fn test_animation() {
+        let mut animation = Animation::new(vec![[0, 0, 0], [255, 0, 0], [0, 0, 255]], 25, 2);
+        assert_eq!(animation.delay(), 4);
+        for i in 0..3 {
+            animation.push(&DenseGrid::from_fn(3, 2, |x, _| x == i), |&on| on as u8);
+        }
+        assert_eq!(animation.len(), 3);
+        let mut gif = vec![];
+        animation.write(&mut gif).unwrap();
+        assert!(gif.starts_with(b"GIF89a\x06\0\x04\0\x91\0\0\0\0\0\xff\0\0\0\0\xff\0\0\0"));
+        assert!(gif.ends_with(b"\0\x3b"));
+        assert_eq!(gif.windows(2).filter(|w| w == b"\x21\xf9").count(), 3);
+    }
+}
@@ -0,0 +1,487 @@
+//! 2D grids, dense ones for images and maps known in advance and sparse ones
+//! for maps discovered as a robot moves, with `y` growing downwards.
+
+use std::{collections::HashMap, error::Error, fmt};
+
+/// The four orthogonal moves
+pub const ORTHOGONAL: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// The eight moves to the surrounding cells
+pub const SURROUNDING: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridError {
+    /// A character without a cell value, with its 1-based line and column
+    Cell {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    /// A line whose length differs from the first one
+    Ragged { line: usize },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::Cell {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "invalid cell {:?} at line {}, column {}",
+                found, line, column
+            ),
+            GridError::Ragged { line } => write!(f, "line {} has not the grid width", line),
+        }
+    }
+}
+
+impl Error for GridError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseGrid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone + Default> DenseGrid<T> {
+    pub fn new(width: usize, height: usize) -> Self {
+        DenseGrid::filled(width, height, T::default())
+    }
+}
+
+impl<T: Clone> DenseGrid<T> {
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        DenseGrid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Grid whose cell `(x, y)` is `f(x, y)`
+    pub fn from_fn<F: Fn(usize, usize) -> T>(width: usize, height: usize, f: F) -> Self {
+        DenseGrid {
+            width,
+            height,
+            cells: (0..width * height)
+                .map(|i| f(i % width, i / width))
+                .collect(),
+        }
+    }
+
+    /// Quarter turn clockwise
+    pub fn rotate_right(&self) -> Self {
+        DenseGrid::from_fn(self.height, self.width, |x, y| {
+            self.cells[(self.height - 1 - x) * self.width + y].clone()
+        })
+    }
+
+    /// Quarter turn counterclockwise
+    pub fn rotate_left(&self) -> Self {
+        DenseGrid::from_fn(self.height, self.width, |x, y| {
+            self.cells[x * self.width + self.width - 1 - y].clone()
+        })
+    }
+
+    pub fn rotate_180(&self) -> Self {
+        let mut cells = self.cells.clone();
+        cells.reverse();
+        DenseGrid { cells, ..*self }
+    }
+
+    /// Mirror left to right
+    pub fn flip_horizontal(&self) -> Self {
+        DenseGrid::from_fn(self.width, self.height, |x, y| {
+            self.cells[y * self.width + self.width - 1 - x].clone()
+        })
+    }
+
+    /// Mirror top to bottom
+    pub fn flip_vertical(&self) -> Self {
+        DenseGrid::from_fn(self.width, self.height, |x, y| {
+            self.cells[(self.height - 1 - y) * self.width + x].clone()
+        })
+    }
+}
+
+impl<T> DenseGrid<T> {
+    /// One line per row, `cell` giving the value of each character
+    pub fn parse<F: Fn(char) -> Option<T>>(text: &str, cell: F) -> Result<Self, GridError> {
+        let mut cells = vec![];
+        let mut width = None;
+        let mut height = 0;
+        for (l, line) in text.lines().enumerate() {
+            let start = cells.len();
+            for (c, found) in line.chars().enumerate() {
+                cells.push(cell(found).ok_or(GridError::Cell {
+                    line: l + 1,
+                    column: c + 1,
+                    found,
+                })?);
+            }
+            if *width.get_or_insert(cells.len() - start) != cells.len() - start {
+                return Err(GridError::Ragged { line: l + 1 });
+            }
+            height += 1;
+        }
+        Ok(DenseGrid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            Some(&mut self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        *self.get_mut(x, y).expect("cell out of the grid") = value;
+    }
+
+    /// Cells with their coordinates, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| (i % width, i / width, v))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Cells of the grid reached by the given moves
+    pub fn neighbours(&self, x: usize, y: usize, moves: &[(i64, i64)]) -> Vec<(usize, usize, &T)> {
+        moves
+            .iter()
+            .map(|&(dx, dy)| (x as i64 + dx, y as i64 + dy))
+            .filter(|&(x, y)| self.contains(x, y))
+            .map(|(x, y)| (x as usize, y as usize, &self[(x as usize, y as usize)]))
+            .collect()
+    }
+
+    pub fn map<U, F: Fn(&T) -> U>(&self, f: F) -> DenseGrid<U> {
+        DenseGrid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// One line per row, `palette` giving the character of each cell
+    pub fn render<F: Fn(&T) -> char>(&self, palette: F) -> Vec<String> {
+        self.rows()
+            .take(self.height)
+            .map(|row| row.iter().map(&palette).collect())
+            .collect()
+    }
+}
+
+impl<T> std::ops::Index<(usize, usize)> for DenseGrid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).expect("cell out of the grid")
+    }
+}
+
+/// Smallest rectangle holding a set of cells, bounds included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub x_min: i64,
+    pub x_max: i64,
+    pub y_min: i64,
+    pub y_max: i64,
+}
+
+impl Bounds {
+    pub fn point(x: i64, y: i64) -> Self {
+        Bounds {
+            x_min: x,
+            x_max: x,
+            y_min: y,
+            y_max: y,
+        }
+    }
+
+    pub fn extend(&mut self, x: i64, y: i64) {
+        self.x_min = self.x_min.min(x);
+        self.x_max = self.x_max.max(x);
+        self.y_min = self.y_min.min(y);
+        self.y_max = self.y_max.max(y);
+    }
+
+    /// Grow to hold the other bounds as well
+    pub fn merge(&mut self, other: &Bounds) {
+        self.extend(other.x_min, other.y_min);
+        self.extend(other.x_max, other.y_max);
+    }
+
+    pub fn width(&self) -> usize {
+        (self.x_max - self.x_min + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.y_max - self.y_min + 1) as usize
+    }
+
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        (self.x_min..=self.x_max).contains(&x) && (self.y_min..=self.y_max).contains(&y)
+    }
+}
+
+/// Cells anywhere on the plane, only the ones set being stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<(i64, i64), T>,
+    bounds: Option<Bounds>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        SparseGrid::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Bounds of the cells set so far, `None` when empty
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> Option<&T> {
+        self.cells.get(&(x, y))
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, value: T) {
+        match &mut self.bounds {
+            Some(b) => b.extend(x, y),
+            None => self.bounds = Some(Bounds::point(x, y)),
+        }
+        self.cells.insert((x, y), value);
+    }
+
+    /// Cells in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (i64, i64, &T)> {
+        self.cells.iter().map(|(&(x, y), v)| (x, y, v))
+    }
+
+    /// Same cells and bounds with every value converted
+    pub fn map<U, F: Fn(&T) -> U>(&self, f: F) -> SparseGrid<U> {
+        SparseGrid {
+            cells: self.cells.iter().map(|(&p, v)| (p, f(v))).collect(),
+            bounds: self.bounds,
+        }
+    }
+
+    /// Cells set among the ones reached by the given moves
+    pub fn neighbours(&self, x: i64, y: i64, moves: &[(i64, i64)]) -> Vec<(i64, i64, &T)> {
+        moves
+            .iter()
+            .map(|&(dx, dy)| (x + dx, y + dy))
+            .filter_map(|(x, y)| self.get(x, y).map(|v| (x, y, v)))
+            .collect()
+    }
+
+    /// Dense copy of the cells within the bounds, the missing ones being
+    /// `fill`
+    pub fn to_dense(&self, fill: T) -> DenseGrid<T>
+    where
+        T: Clone,
+    {
+        match self.bounds {
+            Some(b) => self.crop(&b, fill),
+            None => DenseGrid::filled(0, 0, fill),
+        }
+    }
+
+    /// Dense copy of the cells within the given bounds, the missing ones
+    /// being `fill`
+    pub fn crop(&self, bounds: &Bounds, fill: T) -> DenseGrid<T>
+    where
+        T: Clone,
+    {
+        DenseGrid::from_fn(bounds.width(), bounds.height(), |x, y| {
+            self.get(x as i64 + bounds.x_min, y as i64 + bounds.y_min)
+                .unwrap_or(&fill)
+                .clone()
+        })
+    }
+
+    /// One line per row within the bounds, `palette` getting `None` for the
+    /// cells not set
+    pub fn render<F: Fn(Option<&T>) -> char>(&self, palette: F) -> Vec<String> {
+        match self.bounds {
+            Some(b) => (b.y_min..=b.y_max)
+                .map(|y| {
+                    (b.x_min..=b.x_max)
+                        .map(|x| palette(self.get(x, y)))
+                        .collect()
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+/// Dense copies of the grids sharing the bounds of all of them, like the
+/// frames of an animation
+pub fn crop_all<T: Clone>(grids: &[SparseGrid<T>], fill: T) -> Vec<DenseGrid<T>> {
+    let bounds = grids.iter().filter_map(SparseGrid::bounds).fold(
+        None,
+        |acc: Option<Bounds>, b| match acc {
+            Some(mut acc) => {
+                acc.merge(&b);
+                Some(acc)
+            }
+            None => Some(b),
+        },
+    );
+    match bounds {
+        Some(b) => grids.iter().map(|g| g.crop(&b, fill.clone())).collect(),
+        None => grids
+            .iter()
+            .map(|_| DenseGrid::filled(0, 0, fill.clone()))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn digits(text: &str) -> DenseGrid<u32> {
+        DenseGrid::parse(text, |c| c.to_digit(10)).unwrap()
+    }
+
+    fn show(grid: &DenseGrid<u32>) -> String {
+        grid.render(|&d| std::char::from_digit(d, 10).unwrap())
+            .join("\n")
+    }
+
+    #[test]
+    fn test_dense() {
+        let grid = digits("123\n456");
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(2, 1), Some(&6));
+        assert_eq!(grid.get(3, 1), None);
+        assert_eq!(grid[(0, 1)], 4);
+        assert_eq!(show(&grid.rotate_right()), "41\n52\n63");
+        assert_eq!(show(&grid.rotate_left()), "36\n25\n14");
+        assert_eq!(show(&grid.rotate_180()), "654\n321");
+        assert_eq!(show(&grid.flip_horizontal()), "321\n654");
+        assert_eq!(show(&grid.flip_vertical()), "456\n123");
+        let around: Vec<u32> = grid
+            .neighbours(0, 0, &ORTHOGONAL)
+            .iter()
+            .map(|&(_, _, &v)| v)
+            .collect();
+        assert_eq!(around, vec![2, 4]);
+        assert_eq!(grid.neighbours(1, 1, &SURROUNDING).len(), 5);
+        assert_eq!(grid.iter().filter(|&(_, _, &v)| v % 2 == 0).count(), 3);
+
+        assert_eq!(
+            DenseGrid::parse("12\n3x", |c| c.to_digit(10)),
+            Err(GridError::Cell {
+                line: 2,
+                column: 2,
+                found: 'x'
+            })
+        );
+        assert_eq!(
+            DenseGrid::parse("12\n3", |c| c.to_digit(10)),
+            Err(GridError::Ragged { line: 2 })
+        );
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        grid.set(-1, 2, 'a');
+        grid.set(1, 0, 'b');
+        assert_eq!(
+            grid.bounds(),
+            Some(Bounds {
+                x_min: -1,
+                x_max: 1,
+                y_min: 0,
+                y_max: 2
+            })
+        );
+        assert_eq!(grid.neighbours(0, 0, &ORTHOGONAL), vec![(1, 0, &'b')]);
+        assert_eq!(
+            grid.render(|c| *c.unwrap_or(&'.')),
+            vec!["..b", "...", "a.."]
+        );
+        assert_eq!(
+            grid.to_dense('.').render(|&c| c),
+            grid.render(|c| *c.unwrap_or(&'.'))
+        );
+        assert_eq!(
+            grid.map(|c| c.to_ascii_uppercase())
+                .to_dense('.')
+                .render(|&c| c),
+            vec!["..B", "...", "A.."]
+        );
+        let mut other = SparseGrid::new();
+        other.set(2, 0, 'c');
+        let frames = crop_all(&[grid, other], '.');
+        assert_eq!(frames[0].render(|&c| c), vec!["..b.", "....", "a..."]);
+        assert_eq!(frames[1].render(|&c| c), vec!["...c", "....", "...."]);
+    }
+}
@@ -0,0 +1,291 @@
+//! Memory activity heatmap of a running IntCode machine.
+//!
+//! Attach a [`Heatmap`] to a machine to count the reads, writes and executed
+//! instructions of every address. Activity is also cut in frames of a fixed
+//! number of instructions to be replayed in the terminal or saved as an
+//! animated GIF. Writes are drawn in red, reads in blue and executed code
+//! in green, on a log scale.
+
+use super::gif::Animation;
+use super::grid::DenseGrid;
+use super::image::{Image, Rgb, BLACK};
+use super::intcode::{IntCode, Observer};
+use std::{
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frame {
+    pub reads: Vec<u32>,
+    pub writes: Vec<u32>,
+    pub executed: Vec<u32>,
+}
+
+fn bump(counts: &mut Vec<u32>, addr: usize) {
+    if addr >= counts.len() {
+        counts.resize(addr + 1, 0);
+    }
+    counts[addr] += 1;
+}
+
+fn get(counts: &[u32], addr: usize) -> u32 {
+    *counts.get(addr).unwrap_or(&0)
+}
+
+/// Levels of a channel in the GIF palette, 0 then the range used by `scale`
+const LEVELS: [u8; 6] = [0, 64, 112, 160, 207, 255];
+
+/// Palette index of the closest colour with every channel in `LEVELS`
+fn quantize(colour: Rgb) -> u8 {
+    colour.iter().fold(0, |index, &c| {
+        let level = (0..LEVELS.len())
+            .min_by_key(|&l| (i32::from(LEVELS[l]) - i32::from(c)).abs())
+            .unwrap_or(0);
+        index * LEVELS.len() as u8 + level as u8
+    })
+}
+
+fn scale(count: u32, max: u32) -> u8 {
+    if count == 0 {
+        0
+    } else {
+        // Keep some light for a single access
+        let ratio = f64::from(count).ln_1p() / f64::from(max).ln_1p();
+        (64.0 + 191.0 * ratio) as u8
+    }
+}
+
+impl Frame {
+    fn len(&self) -> usize {
+        self.reads
+            .len()
+            .max(self.writes.len())
+            .max(self.executed.len())
+    }
+
+    fn add(&mut self, other: &Frame) {
+        let len = other.len();
+        for (counts, others) in [
+            (&mut self.reads, &other.reads),
+            (&mut self.writes, &other.writes),
+            (&mut self.executed, &other.executed),
+        ] {
+            counts.resize(counts.len().max(len), 0);
+            for (c, o) in counts.iter_mut().zip(others) {
+                *c += o;
+            }
+        }
+    }
+
+    fn max(&self) -> u32 {
+        self.reads
+            .iter()
+            .chain(&self.writes)
+            .chain(&self.executed)
+            .cloned()
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Colour of every address as RGB
+    pub fn colours(&self, len: usize) -> Vec<[u8; 3]> {
+        let max = self.max();
+        (0..len)
+            .map(|a| {
+                [
+                    scale(get(&self.writes, a), max),
+                    scale(get(&self.executed, a), max),
+                    scale(get(&self.reads, a), max),
+                ]
+            })
+            .collect()
+    }
+
+    /// Render with ANSI true colour backgrounds, `width` addresses per line
+    pub fn render_ansi(&self, len: usize, width: usize) -> String {
+        let mut res = String::new();
+        for line in self.colours(len).chunks(width) {
+            for [r, g, b] in line {
+                res.push_str(&format!("\x1b[48;2;{};{};{}m ", r, g, b));
+            }
+            res.push_str("\x1b[0m\n");
+        }
+        res
+    }
+
+    /// One pixel per address, `width` addresses per line, to be saved with
+    /// the `image` module
+    pub fn image(&self, len: usize, width: usize) -> Image {
+        let colours = self.colours(len);
+        DenseGrid::from_fn(width, len.div_ceil(width), |x, y| {
+            *colours.get(y * width + x).unwrap_or(&BLACK)
+        })
+    }
+}
+
+pub struct Heatmap {
+    frame_every: usize,
+    steps: usize,
+    current: Frame,
+    frames: Vec<Frame>,
+}
+
+impl Heatmap {
+    /// Cut a new frame every `frame_every` instructions
+    pub fn new(frame_every: usize) -> Self {
+        Heatmap {
+            frame_every,
+            steps: 0,
+            current: Frame::default(),
+            frames: vec![],
+        }
+    }
+
+    /// Frames recorded so far, including the unfinished one
+    pub fn frames(&self) -> Vec<Frame> {
+        let mut frames = self.frames.clone();
+        if self.current.len() > 0 {
+            frames.push(self.current.clone());
+        }
+        frames
+    }
+
+    /// Activity over the whole run
+    pub fn total(&self) -> Frame {
+        let mut total = Frame::default();
+        for f in self.frames.iter().chain(Some(&self.current)) {
+            total.add(f);
+        }
+        total
+    }
+
+    /// Number of addresses touched, the size of the rendered memory
+    pub fn len(&self) -> usize {
+        self.frames
+            .iter()
+            .map(Frame::len)
+            .fold(self.current.len(), usize::max)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Replay the frames in the terminal, redrawing in place
+    pub fn animate<W: Write>(&self, out: &mut W, width: usize, delay: Duration) -> io::Result<()> {
+        let len = self.len();
+        write!(out, "\x1b[2J")?;
+        for frame in self.frames() {
+            write!(out, "\x1b[H{}", frame.render_ansi(len, width))?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// The frames as a GIF animation, one `scale` pixels square per address
+    pub fn animation(&self, width: usize, fps: u32, scale: usize) -> Animation {
+        let palette = LEVELS
+            .iter()
+            .flat_map(|&r| {
+                LEVELS
+                    .iter()
+                    .flat_map(move |&g| LEVELS.iter().map(move |&b| [r, g, b]))
+            })
+            .collect();
+        let mut animation = Animation::new(palette, fps, scale);
+        let len = self.len();
+        for frame in self.frames() {
+            animation.push(&frame.image(len, width), |&c| quantize(c));
+        }
+        animation
+    }
+}
+
+impl Observer for Heatmap {
+    fn before_instruction(&mut self, machine: &IntCode) {
+        let value = machine.content.get(machine.index).copied().unwrap_or(0);
+        let arity = machine
+            .instructions
+            .get(value % 100)
+            .map_or(0, |i| i.params.len());
+        for addr in machine.index..=machine.index + arity {
+            bump(&mut self.current.executed, addr);
+        }
+    }
+
+    /// The reads and writes of an instruction are in the same frame as it
+    fn after_instruction(&mut self, _machine: &IntCode) {
+        self.steps += 1;
+        if self.steps.is_multiple_of(self.frame_every) {
+            let frame = std::mem::take(&mut self.current);
+            self.frames.push(frame);
+        }
+    }
+
+    fn memory_read(&mut self, addr: usize, _val: i64) {
+        bump(&mut self.current.reads, addr);
+    }
+
+    fn memory_write(&mut self, addr: usize, _val: i64) {
+        bump(&mut self.current.writes, addr);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_heatmap() {
+        let heatmap = Rc::new(RefCell::new(Heatmap::new(2)));
+        let mut i = IntCode::new(vec![1001, 8, 1, 8, 1005, 8, 0, 99, -3], vec![]);
+        i.attach(heatmap.clone());
+        i.execute();
+        let heatmap = heatmap.borrow();
+        let total = heatmap.total();
+        // Three loops of add and jump
+        assert_eq!(get(&total.executed, 0), 3);
+        assert_eq!(get(&total.executed, 4), 3);
+        assert_eq!(get(&total.executed, 7), 1);
+        assert_eq!(get(&total.writes, 8), 3);
+        assert_eq!(get(&total.reads, 8), 6);
+        // Add and jump in each frame, then the stop
+        let frames = heatmap.frames();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(get(&frames[0].writes, 8), 1);
+        assert_eq!(get(&frames[0].reads, 8), 2);
+        assert_eq!(get(&frames[0].executed, 4), 1);
+        assert_eq!(frames[3].reads, vec![]);
+        assert_eq!(frames[3].writes, vec![]);
+        assert_eq!(get(&frames[3].executed, 7), 1);
+        assert_eq!(heatmap.len(), 9);
+
+        let image = total.image(heatmap.len(), 4);
+        assert_eq!((image.width(), image.height()), (4, 3));
+        assert_eq!(image[(3, 2)], BLACK);
+        assert!(image[(0, 2)][0] > 0);
+
+        let mut out = vec![];
+        heatmap
+            .animate(&mut out, 4, Duration::from_millis(0))
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("\x1b[2J\x1b[H"));
+        assert_eq!(out.matches("\x1b[H").count(), 4);
+        assert_eq!(out.matches("\x1b[0m\n").count(), 4 * 3);
+
+        let animation = heatmap.animation(4, 10, 2);
+        assert_eq!(animation.len(), 4);
+        let mut gif = vec![];
+        animation.write(&mut gif).unwrap();
+        // 8x6 pixels with a 256 colours palette
+        assert!(gif.starts_with(b"GIF89a\x08\0\x06\0\xf7"));
+        assert_eq!(gif.windows(2).filter(|w| w == b"\x21\xf9").count(), 4);
+        assert_eq!(quantize(BLACK), 0);
+        assert_eq!(quantize([255, 64, 120]), 5 * 36 + 6 + 2);
+    }
+}
@@ -0,0 +1,210 @@
+//! Save grids as image files, in PNG or in the simpler PPM and PGM formats.
+//!
+//! A grid is first turned into an [`Image`] by [`rasterize`], which gives
+//! each cell a colour from a palette and scales it to a square of pixels.
+
+use super::grid::DenseGrid;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+pub type Rgb = [u8; 3];
+
+pub type Image = DenseGrid<Rgb>;
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+/// Parse a colour written `rrggbb`, with or without a leading `#`
+pub fn parse_colour(s: &str) -> Option<Rgb> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if s.len() != 6 || !s.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&s[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Each cell becomes a `scale` pixels square of the colour given by `palette`
+pub fn rasterize<T, F: Fn(&T) -> Rgb>(grid: &DenseGrid<T>, scale: usize, palette: F) -> Image {
+    let colours = grid.map(palette);
+    DenseGrid::from_fn(grid.width() * scale, grid.height() * scale, |x, y| {
+        colours[(x / scale, y / scale)]
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Png,
+    /// Binary colour Netpbm
+    Ppm,
+    /// Binary grey Netpbm
+    Pgm,
+}
+
+impl Format {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Format> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match &extension[..] {
+            "png" => Some(Format::Png),
+            "ppm" => Some(Format::Ppm),
+            "pgm" => Some(Format::Pgm),
+            _ => None,
+        }
+    }
+}
+
+pub fn write_ppm<W: Write>(out: &mut W, image: &Image) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width(), image.height())?;
+    for (_, _, rgb) in image.iter() {
+        out.write_all(rgb)?;
+    }
+    Ok(())
+}
+
+fn grey([r, g, b]: Rgb) -> u8 {
+    ((299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b)) / 1000) as u8
+}
+
+pub fn write_pgm<W: Write>(out: &mut W, image: &Image) -> io::Result<()> {
+    write!(out, "P5\n{} {}\n255\n", image.width(), image.height())?;
+    let pixels: Vec<u8> = image.iter().map(|(_, _, &rgb)| grey(rgb)).collect();
+    out.write_all(&pixels)
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut body = kind.to_vec();
+    body.extend_from_slice(data);
+    out.write_all(&body)?;
+    out.write_all(&crc32(&body).to_be_bytes())
+}
+
+/// zlib stream of uncompressed deflate blocks, images are small enough
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut res = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() {
+        vec![&[]]
+    } else {
+        data.chunks(0xffff).collect()
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        res.push((i + 1 == blocks.len()) as u8);
+        let len = block.len() as u16;
+        res.extend_from_slice(&len.to_le_bytes());
+        res.extend_from_slice(&(!len).to_le_bytes());
+        res.extend_from_slice(block);
+    }
+    res.extend_from_slice(&adler32(data).to_be_bytes());
+    res
+}
+
+/// 8 bits RGB PNG
+pub fn write_png<W: Write>(out: &mut W, image: &Image) -> io::Result<()> {
+    out.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'])?;
+    let mut header = vec![];
+    header.extend_from_slice(&(image.width() as u32).to_be_bytes());
+    header.extend_from_slice(&(image.height() as u32).to_be_bytes());
+    // Bit depth, colour type, compression, filter and interlace methods
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+    let mut raw = Vec::with_capacity((image.width() * 3 + 1) * image.height());
+    for row in image.rows().take(image.height()) {
+        // No filter
+        raw.push(0);
+        for rgb in row {
+            raw.extend_from_slice(rgb);
+        }
+    }
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+pub fn write<W: Write>(out: &mut W, image: &Image, format: Format) -> io::Result<()> {
+    match format {
+        Format::Png => write_png(out, image),
+        Format::Ppm => write_ppm(out, image),
+        Format::Pgm => write_pgm(out, image),
+    }
+}
+
+/// Save in the format given by the extension of the path
+pub fn save<P: AsRef<Path>>(path: P, image: &Image) -> io::Result<()> {
+    let format = Format::from_path(&path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "unknown image format, use .png, .ppm or .pgm",
+        )
+    })?;
+    let mut out = vec![];
+    write(&mut out, image, format)?;
+    fs::write(path, out)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(
+            zlib_stored(b""),
+            vec![0x78, 1, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let bitmap = DenseGrid::from_fn(2, 1, |x, _| x == 0);
+        let image = rasterize(&bitmap, 2, |&p| if p { WHITE } else { BLACK });
+        assert_eq!((image.width(), image.height()), (4, 2));
+        assert_eq!(image[(1, 1)], WHITE);
+        assert_eq!(image[(2, 0)], BLACK);
+
+        let mut ppm = vec![];
+        write_ppm(&mut ppm, &image).unwrap();
+        assert!(ppm.starts_with(b"P6\n4 2\n255\n\xff\xff\xff"));
+        assert_eq!(ppm.len(), 11 + 4 * 2 * 3);
+        let mut pgm = vec![];
+        write_pgm(&mut pgm, &image).unwrap();
+        assert_eq!(&pgm[11..], &[255, 255, 0, 0, 255, 255, 0, 0]);
+
+        let mut png = vec![];
+        write_png(&mut png, &image).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x04\0\0\0\x02"));
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+
+        assert_eq!(Format::from_path("hull.PNG"), Some(Format::Png));
+        assert_eq!(Format::from_path("hull"), None);
+        assert_eq!(parse_colour("#ff8000"), Some([255, 128, 0]));
+        assert_eq!(parse_colour("ff80"), None);
+    }
+}
@@ -1,4 +1,5 @@
 // use std::io::{stdin, stdout, Write};
+use std::{cell::RefCell, error::Error, fmt, fs, io, io::Write, path::Path, rc::Rc};
 
 #[derive(Debug, PartialEq)]
 pub enum ExitCode {
@@ -6,6 +7,85 @@ pub enum ExitCode {
     Stop,
     AwaitInput,
     UnknowCode,
+    /// A negative address, or one past `MAX_MEMORY`
+    InvalidAddress(i64),
+    /// An arithmetic result out of the `i64` range
+    Overflow,
+}
+
+#[derive(Debug)]
+pub enum ProgramError {
+    /// A token which is not an integer, with its 1-based line and column
+    Invalid {
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    Empty,
+    Io(io::Error),
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramError::Invalid {
+                line,
+                column,
+                token,
+            } => write!(
+                f,
+                "invalid value {:?} at line {}, column {}",
+                token, line, column
+            ),
+            ProgramError::Empty => write!(f, "empty program"),
+            ProgramError::Io(e) => write!(f, "cannot read program: {}", e),
+        }
+    }
+}
+
+impl Error for ProgramError {}
+
+impl From<io::Error> for ProgramError {
+    fn from(e: io::Error) -> Self {
+        ProgramError::Io(e)
+    }
+}
+
+/// Parse a program made of integers separated by commas or line breaks.
+/// Whitespace around values is ignored, as is a trailing comma at the end of
+/// a line, and `#` starts a comment running to the end of the line.
+pub fn parse_program(input: &str) -> Result<Vec<i64>, ProgramError> {
+    let mut program = vec![];
+    for (l, line) in input.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or("");
+        if code.trim().is_empty() {
+            continue;
+        }
+        let tokens: Vec<&str> = code.split(',').collect();
+        let mut column = 1;
+        for (i, token) in tokens.iter().enumerate() {
+            let value = token.trim();
+            let trailing = i + 1 == tokens.len() && i > 0;
+            if !(value.is_empty() && trailing) {
+                let start = column + token.chars().take_while(|c| c.is_whitespace()).count();
+                program.push(value.parse().map_err(|_| ProgramError::Invalid {
+                    line: l + 1,
+                    column: start,
+                    token: value.to_string(),
+                })?);
+            }
+            column += token.chars().count() + 1;
+        }
+    }
+    if program.is_empty() {
+        return Err(ProgramError::Empty);
+    }
+    Ok(program)
+}
+
+/// Read and parse a program file
+pub fn load_program<P: AsRef<Path>>(path: P) -> Result<Vec<i64>, ProgramError> {
+    parse_program(&fs::read_to_string(path)?)
 }
 
 #[derive(Debug, Clone)]
@@ -16,85 +96,368 @@ pub struct IntCode {
     pub manual_input_index: usize,
     pub output: Vec<i64>,
     pub relative_base: i64,
+    pub instructions: Rc<InstructionSet>,
+    pub observers: Observers,
+}
+
+/// Callbacks on the events of a running machine, all of them doing nothing
+/// by default so an observer only implements what it cares about.
+pub trait Observer {
+    fn before_instruction(&mut self, _machine: &IntCode) {}
+    fn after_instruction(&mut self, _machine: &IntCode) {}
+    fn memory_read(&mut self, _addr: usize, _val: i64) {}
+    fn memory_write(&mut self, _addr: usize, _val: i64) {}
+    fn input_consumed(&mut self, _val: i64) {}
+    fn output_produced(&mut self, _val: i64) {}
+    fn halt(&mut self, _code: &ExitCode) {}
+}
+
+/// Observers attached to a machine, shared with its clones
+#[derive(Clone, Default)]
+pub struct Observers(Vec<Rc<RefCell<dyn Observer>>>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+/// Number of executed instructions, the ones paused on a missing input being
+/// only counted once they run
+#[derive(Debug, Default)]
+pub struct Counter(pub usize);
+
+impl Observer for Counter {
+    fn after_instruction(&mut self, _machine: &IntCode) {
+        self.0 += 1;
+    }
+
+    fn halt(&mut self, code: &ExitCode) {
+        if *code == ExitCode::Stop {
+            self.0 += 1;
+        }
+    }
+}
+
+/// Print every executed instruction with its raw parameters
+pub struct Tracer<W: Write> {
+    out: W,
+}
+
+impl<W: Write> Tracer<W> {
+    pub fn new(out: W) -> Self {
+        Tracer { out }
+    }
+}
+
+impl<W: Write> Observer for Tracer<W> {
+    fn before_instruction(&mut self, machine: &IntCode) {
+        let value = machine.content.get(machine.index).copied().unwrap_or(0);
+        let (name, arity) = match machine.instructions.get(value % 100) {
+            Some(i) => (i.name, i.params.len()),
+            None => ("unknown", 0),
+        };
+        let end = (machine.index + 1 + arity).min(machine.content.len());
+        let _ = writeln!(
+            self.out,
+            "{:>6} {:<14} {:?}",
+            machine.index,
+            name,
+            machine.content.get(machine.index..end).unwrap_or(&[])
+        );
+    }
+
+    fn input_consumed(&mut self, val: i64) {
+        let _ = writeln!(self.out, "{:>6} <- {}", "", val);
+    }
+
+    fn output_produced(&mut self, val: i64) {
+        let _ = writeln!(self.out, "{:>6} -> {}", "", val);
+    }
+
+    fn halt(&mut self, code: &ExitCode) {
+        let _ = writeln!(self.out, "{:>6} {:?}", "", code);
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
-enum ParamMode {
+pub enum ParamMode {
     Position,
     Immediate,
     Relative,
 }
 
 impl ParamMode {
-    fn from_int(m: i64) -> ParamMode {
+    fn from_int(m: i64) -> Option<ParamMode> {
         match m {
-            0 => ParamMode::Position,
-            1 => ParamMode::Immediate,
-            2 => ParamMode::Relative,
-            _ => panic!("Unknow param mode"),
+            0 => Some(ParamMode::Position),
+            1 => Some(ParamMode::Immediate),
+            2 => Some(ParamMode::Relative),
+            _ => None,
         }
     }
 }
 
-#[derive(PartialEq, Debug)]
-enum OperationType {
-    Add,
-    Mult,
-    Input,
-    Output,
-    JumpTrue,
-    JumpFalse,
-    LessThan,
-    Equals,
-    AddToRelative,
-    Stop,
-    Unknown,
+/// How an instruction uses a parameter: `Read` parameters are resolved to a
+/// value, `Write` ones to the address to write to.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ParamKind {
+    Read,
+    Write,
+}
+
+/// What the machine does once an instruction handler returns
+#[derive(Debug, PartialEq)]
+pub enum Step {
+    /// Move to the next instruction
+    Next,
+    /// Move the instruction pointer to the address
+    Jump(usize),
+    /// Move to the next instruction and pause with the exit code
+    Yield(ExitCode),
+    /// Pause on this instruction with the exit code
+    Halt(ExitCode),
 }
 
-impl OperationType {
-    fn from_int(o: i64) -> OperationType {
-        match o {
-            99 => OperationType::Stop,
-            1 => OperationType::Add,
-            2 => OperationType::Mult,
-            3 => OperationType::Input,
-            4 => OperationType::Output,
-            5 => OperationType::JumpTrue,
-            6 => OperationType::JumpFalse,
-            7 => OperationType::LessThan,
-            8 => OperationType::Equals,
-            9 => OperationType::AddToRelative,
-            _ => OperationType::Unknown,
+pub type Handler = Rc<dyn Fn(&mut IntCode, &[i64]) -> Step>;
+
+const MAX_PARAMS: usize = 8;
+
+/// Opcodes are the two lowest digits of an instruction
+const OPCODES: usize = 100;
+
+/// Size the memory of a machine may grow to
+pub const MAX_MEMORY: usize = 1 << 20;
+
+/// Memory index of an address
+fn address(addr: i64) -> Result<usize, ExitCode> {
+    if (0..MAX_MEMORY as i64).contains(&addr) {
+        Ok(addr as usize)
+    } else {
+        Err(ExitCode::InvalidAddress(addr))
+    }
+}
+
+#[derive(Clone)]
+pub struct Instruction {
+    pub name: &'static str,
+    pub params: Vec<ParamKind>,
+    /// Modes accepted for each of the parameters
+    pub modes: Vec<ParamMode>,
+    /// Pauses with `AwaitInput` while no input is pending, before observers
+    /// are told about it
+    pub reads_input: bool,
+    handler: Handler,
+}
+
+impl Instruction {
+    pub fn new<F>(name: &'static str, params: Vec<ParamKind>, handler: F) -> Self
+    where
+        F: Fn(&mut IntCode, &[i64]) -> Step + 'static,
+    {
+        assert!(params.len() <= MAX_PARAMS, "Too many parameters");
+        Instruction {
+            name,
+            params,
+            modes: vec![ParamMode::Position],
+            reads_input: false,
+            handler: Rc::new(handler),
         }
     }
+
+    /// Only run once an input is pending
+    pub fn reading_input(mut self) -> Self {
+        self.reads_input = true;
+        self
+    }
+
+    /// Replace the accepted modes, the position mode only by default
+    pub fn with_modes(mut self, modes: &[ParamMode]) -> Self {
+        self.modes = modes.to_vec();
+        self
+    }
+
+    fn mode(&self, m: i64) -> Option<ParamMode> {
+        ParamMode::from_int(m).filter(|m| self.modes.contains(m))
+    }
 }
 
-#[derive(Debug)]
-struct Operation {
-    mode: OperationType,
-    params_mode: Vec<ParamMode>,
+/// Opcodes understood by a machine
+#[derive(Clone)]
+pub struct InstructionSet {
+    instructions: Vec<Option<Instruction>>,
 }
 
-fn parse_parameter(p: i64) -> Operation {
-    let p = &format!("{:05}", p)[..];
-    let p3type = p[..1].parse::<i64>().unwrap();
-    let p2type = p[1..2].parse::<i64>().unwrap();
-    let p1type = p[2..3].parse::<i64>().unwrap();
-    let o = p[3..5].parse::<i64>().unwrap();
-    // println!("{} {} {} {}", o, p1type, p2type, p3type);
-    // println!("Opération is {:?}", OperationType::from_int(o));
-    Operation {
-        mode: OperationType::from_int(o),
-        params_mode: vec![
-            ParamMode::from_int(p1type),
-            ParamMode::from_int(p2type),
-            ParamMode::from_int(p3type),
-        ],
+impl fmt::Debug for InstructionSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.instructions
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(o, i)| i.as_ref().map(|i| (o, i.name))),
+            )
+            .finish()
+    }
+}
+
+impl Default for InstructionSet {
+    fn default() -> Self {
+        InstructionSet::full()
+    }
+}
+
+fn binary(name: &'static str, f: fn(i64, i64) -> Option<i64>) -> Instruction {
+    use ParamKind::*;
+    Instruction::new(name, vec![Read, Read, Write], move |m, p| {
+        match f(p[0], p[1]) {
+            Some(value) => {
+                m.write_result_to_addr(p[2] as usize, value);
+                Step::Next
+            }
+            None => Step::Halt(ExitCode::Overflow),
+        }
+    })
+}
+
+fn jump(name: &'static str, when: bool) -> Instruction {
+    use ParamKind::*;
+    Instruction::new(name, vec![Read, Read], move |_, p| {
+        if (p[0] != 0) == when {
+            match address(p[1]) {
+                Ok(addr) => Step::Jump(addr),
+                Err(e) => Step::Halt(e),
+            }
+        } else {
+            Step::Next
+        }
+    })
+}
+
+impl InstructionSet {
+    /// No instruction at all, to be filled with `register`
+    pub fn empty() -> Self {
+        InstructionSet {
+            instructions: vec![None; OPCODES],
+        }
+    }
+
+    /// Add, multiply and stop, position mode only
+    pub fn day2() -> Self {
+        let mut set = InstructionSet::empty();
+        set.register(1, binary("add", i64::checked_add));
+        set.register(2, binary("mult", i64::checked_mul));
+        set.register(
+            99,
+            Instruction::new("stop", vec![], |_, _| Step::Halt(ExitCode::Stop)),
+        );
+        set
+    }
+
+    /// Day 2 with input, output and the immediate mode
+    pub fn day5_part1() -> Self {
+        use ParamKind::*;
+        let mut set = InstructionSet::day2();
+        set.register(
+            3,
+            Instruction::new("input", vec![Write], |m, p| match m.next_input() {
+                Some(input) => {
+                    m.write_result_to_addr(p[0] as usize, input);
+                    Step::Next
+                }
+                None => Step::Halt(ExitCode::AwaitInput),
+            })
+            .reading_input(),
+        );
+        set.register(
+            4,
+            Instruction::new("output", vec![Read], |m, p| {
+                m.display_value(p[0]);
+                Step::Yield(ExitCode::Output(p[0]))
+            }),
+        );
+        set.allow_mode(ParamMode::Immediate);
+        set
+    }
+
+    /// Day 5 part 1 with jumps and comparisons
+    pub fn day5() -> Self {
+        let mut set = InstructionSet::day5_part1();
+        set.register(5, jump("jump-if-true", true));
+        set.register(6, jump("jump-if-false", false));
+        set.register(7, binary("less-than", |a, b| Some((a < b) as i64)));
+        set.register(8, binary("equals", |a, b| Some((a == b) as i64)));
+        set.allow_mode(ParamMode::Immediate);
+        set
+    }
+
+    /// Complete instruction set of day 9, with the relative mode
+    pub fn full() -> Self {
+        let mut set = InstructionSet::day5();
+        set.register(
+            9,
+            Instruction::new("relative-base", vec![ParamKind::Read], |m, p| {
+                match m.relative_base.checked_add(p[0]) {
+                    Some(base) => {
+                        m.relative_base = base;
+                        Step::Next
+                    }
+                    None => Step::Halt(ExitCode::Overflow),
+                }
+            }),
+        );
+        set.allow_mode(ParamMode::Immediate);
+        set.allow_mode(ParamMode::Relative);
+        set
+    }
+
+    /// Panics if the opcode has more than two digits
+    pub fn register(&mut self, opcode: usize, instruction: Instruction) {
+        assert!(
+            opcode < OPCODES,
+            "opcode {} has more than two digits",
+            opcode
+        );
+        self.instructions[opcode] = Some(instruction);
+    }
+
+    /// Panics if the opcode has more than two digits
+    pub fn disable(&mut self, opcode: usize) {
+        assert!(
+            opcode < OPCODES,
+            "opcode {} has more than two digits",
+            opcode
+        );
+        self.instructions[opcode] = None;
+    }
+
+    /// Accept the mode in every instruction registered so far
+    pub fn allow_mode(&mut self, mode: ParamMode) {
+        for instruction in self.instructions.iter_mut().flatten() {
+            if !instruction.modes.contains(&mode) {
+                instruction.modes.push(mode);
+            }
+        }
+    }
+
+    pub fn get(&self, opcode: i64) -> Option<&Instruction> {
+        if opcode < 0 {
+            return None;
+        }
+        self.instructions.get(opcode as usize)?.as_ref()
     }
 }
 
 impl IntCode {
     pub fn new(input: Vec<i64>, manual_input: Vec<i64>) -> Self {
+        IntCode::with_instructions(input, manual_input, InstructionSet::default())
+    }
+
+    pub fn with_instructions(
+        input: Vec<i64>,
+        manual_input: Vec<i64>,
+        instructions: InstructionSet,
+    ) -> Self {
         IntCode {
             index: 0,
             content: input,
@@ -102,173 +465,128 @@ impl IntCode {
             manual_input_index: 0,
             output: vec![],
             relative_base: 0,
+            instructions: Rc::new(instructions),
+            observers: Observers::default(),
         }
     }
+
+    /// Attach an observer, keep a clone of the `Rc` to read it afterwards
+    pub fn attach<O: Observer + 'static>(&mut self, observer: Rc<RefCell<O>>) {
+        self.observers.0.push(observer);
+    }
+
+    fn notify<F: Fn(&mut dyn Observer)>(&self, f: F) {
+        for o in &self.observers.0 {
+            f(&mut *o.borrow_mut());
+        }
+    }
+
     pub fn execute(&mut self) -> ExitCode {
         loop {
-            if let Err(e) = self.execute_operation() {
+            if let Some(e) = self.step() {
                 return e;
             }
         }
     }
 
+    /// Execute a single instruction, returning the exit code if the machine pauses
+    pub fn step(&mut self) -> Option<ExitCode> {
+        let e = self.execute_operation().err()?;
+        if !matches!(e, ExitCode::Output(_) | ExitCode::AwaitInput) {
+            self.notify(|o| o.halt(&e));
+        }
+        Some(e)
+    }
+
     fn execute_operation(&mut self) -> Result<bool, ExitCode> {
-        // println!(
-        //     "{} - OP {} {:?} {}",
-        //     self.index,
-        //     self.content[self.index],
-        //     self.manual_input,
-        //     self.manual_input_index
-        //     // &self.content[..]
-        // );
-        let o_mode = OperationType::from_int(self.content[self.index]);
-        let o = if OperationType::Unknown == o_mode {
-            parse_parameter(self.content[self.index])
-        } else {
-            Operation {
-                mode: o_mode,
-                params_mode: vec![ParamMode::Position; 3],
-            }
-        };
-        // println!("EXECUTING OP {:?}", o);
-        self.run_operation(o)
-    }
-
-    fn run_operation(&mut self, o: Operation) -> Result<bool, ExitCode> {
-        // println!("{:?}", o);
-        match o.mode {
-            OperationType::Add => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                let val2 = self.get_param(2, o.params_mode[1]);
-                // println!("{} + {}", val1, val2);
-                let i = self.get_addr(3, o.params_mode[2]);
-                self.write_result_to_addr(i as usize, val1 + val2);
-                self.index += 4;
-                Ok(true)
-            }
-            OperationType::Mult => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                let val2 = self.get_param(2, o.params_mode[1]);
-                // println!("{} * {}", val1, val2);
-                let i = self.get_addr(3, o.params_mode[2]);
-                self.write_result_to_addr(i as usize, val1 * val2);
-                self.index += 4;
-                Ok(true)
-            }
-            OperationType::Input => {
-                // println!("INPUT {:?} {}", o, self.content[self.index + 1]);
-                let a = self.get_addr(1, o.params_mode[0]);
-                self.get_input(a as usize)?;
-                self.index += 2;
-                Ok(true)
-            }
-            OperationType::Output => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                self.display_value(val1);
-                self.index += 2;
-                Err(ExitCode::Output(val1))
-            }
-            OperationType::JumpTrue => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                let val2 = self.get_param(2, o.params_mode[1]);
-                // println!("{} {}", val1, val2);
-                if val1 != 0 {
-                    self.index = val2 as usize;
-                } else {
-                    self.index += 3;
-                }
-                Ok(true)
-            }
-            OperationType::JumpFalse => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                let val2 = self.get_param(2, o.params_mode[1]);
-                if val1 == 0 {
-                    self.index = val2 as usize;
-                } else {
-                    self.index += 3;
-                }
-                Ok(true)
-            }
-            OperationType::LessThan => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                let val2 = self.get_param(2, o.params_mode[1]);
-                let addr = self.get_addr(3, o.params_mode[2]);
-                self.write_result_to_addr(addr as usize, (val1 < val2) as i64);
-                self.index += 4;
-                Ok(true)
-            }
-            OperationType::Equals => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                let val2 = self.get_param(2, o.params_mode[1]);
-                let addr = self.get_addr(3, o.params_mode[2]);
-                // println!("{} == {} -> {} {} {:?}", val1, val2, addr, self.content[self.index + 3], o.params_mode[2]);
-                self.write_result_to_addr(addr as usize, (val1 == val2) as i64);
-                self.index += 4;
+        // Memory past the program is zero, which is no instruction
+        let value = self.content.get(self.index).copied().unwrap_or(0);
+        let set = Rc::clone(&self.instructions);
+        let instruction = set.get(value % 100);
+        // Observers hear of an instruction once, when it runs
+        if instruction.is_some_and(|i| i.reads_input) && !self.has_input() {
+            return Err(ExitCode::AwaitInput);
+        }
+        self.notify(|o| o.before_instruction(self));
+        let instruction = instruction.ok_or(ExitCode::UnknowCode)?;
+        let mut params = [0; MAX_PARAMS];
+        let mut digits = value / 100;
+        for (i, kind) in instruction.params.iter().enumerate() {
+            let mode = instruction.mode(digits % 10).ok_or(ExitCode::UnknowCode)?;
+            digits /= 10;
+            params[i] = match kind {
+                ParamKind::Read => self.get_param(i + 1, mode)?,
+                ParamKind::Write => self.get_addr(i + 1, mode)?,
+            };
+        }
+        let res = match (instruction.handler)(self, &params[..instruction.params.len()]) {
+            Step::Next => {
+                self.index += 1 + instruction.params.len();
                 Ok(true)
             }
-            OperationType::AddToRelative => {
-                let val1 = self.get_param(1, o.params_mode[0]);
-                self.relative_base += val1;
-                self.index += 2;
+            Step::Jump(addr) => {
+                self.index = addr;
                 Ok(true)
             }
-            OperationType::Stop => Err(ExitCode::Stop),
-            _ => {
-                println!("Unknown op code");
-                Err(ExitCode::UnknowCode)
+            Step::Yield(e) => {
+                self.index += 1 + instruction.params.len();
+                Err(e)
             }
-        }
+            Step::Halt(e) => return Err(e),
+        };
+        self.notify(|o| o.after_instruction(self));
+        res
     }
 
-    fn get_param(&self, i: usize, mode: ParamMode) -> i64 {
-        // println!("Get param {} by {:?}", self.index + i, mode);
-        let mut val = self.content[self.index + i];
-        // println!("VALUE {}", val);
-        if mode == ParamMode::Position {
-            val = if val >= self.content.len() as i64 {
-                0
-            } else {
-                self.content[val as usize]
-            }
-        } else if mode == ParamMode::Relative {
-            let index = self.relative_base + val;
-            val = if index >= self.content.len() as i64 {
-                0
-            } else {
-                self.content[index as usize]
-            }
-        }
-        val
+    /// Raw value of the i-th parameter
+    fn raw_param(&self, i: usize) -> i64 {
+        self.content.get(self.index + i).copied().unwrap_or(0)
     }
 
-    fn get_addr(&self, i: usize, mode: ParamMode) -> i64 {
-        // println!("Get addr {} by {:?}", self.index + i, mode);
-        match mode {
-            ParamMode::Position | ParamMode::Immediate => self.content[self.index + i],
-            ParamMode::Relative => self.content[self.index + i] + self.relative_base,
+    fn get_param(&self, i: usize, mode: ParamMode) -> Result<i64, ExitCode> {
+        if mode == ParamMode::Immediate {
+            return Ok(self.raw_param(i));
         }
+        let addr = address(self.get_addr(i, mode)?)?;
+        let val = self.content.get(addr).copied().unwrap_or(0);
+        self.notify(|o| o.memory_read(addr, val));
+        Ok(val)
+    }
+
+    /// Address given by the i-th parameter, checked to be in memory
+    fn get_addr(&self, i: usize, mode: ParamMode) -> Result<i64, ExitCode> {
+        let addr = match mode {
+            ParamMode::Position | ParamMode::Immediate => self.raw_param(i),
+            ParamMode::Relative => self
+                .raw_param(i)
+                .checked_add(self.relative_base)
+                .ok_or(ExitCode::Overflow)?,
+        };
+        address(addr).map(|a| a as i64)
     }
 
-    fn write_result_to_addr(&mut self, i: usize, val: i64) {
+    pub fn write_result_to_addr(&mut self, i: usize, val: i64) {
         if i >= self.content.len() {
             self.content.resize(i + 1, 0);
         }
         self.content[i] = val;
+        self.notify(|o| o.memory_write(i, val));
     }
 
     pub fn push_input(&mut self, input: i64) {
         self.manual_input.push(input)
     }
 
-    fn get_input(&mut self, address: usize) -> Result<(), ExitCode> {
-        // println!("Get inputs");
-        if self.manual_input_index >= self.manual_input.len() {
-            return Err(ExitCode::AwaitInput);
-        }
-        let input = self.manual_input[self.manual_input_index];
-        // println!("INPUT = {}", input);
-        self.write_result_to_addr(address, input);
+    /// Whether an input was pushed and not consumed yet
+    pub fn has_input(&self) -> bool {
+        self.manual_input_index < self.manual_input.len()
+    }
+
+    /// Consume the next pushed input, if any
+    pub fn next_input(&mut self) -> Option<i64> {
+        let input = *self.manual_input.get(self.manual_input_index)?;
         self.manual_input_index += 1;
+        self.notify(|o| o.input_consumed(input));
         // } else {
         //     let mut s = String::new();
         //     print!("Please enter some text: ");
@@ -285,12 +603,216 @@ impl IntCode {
         //     println!("You typed: {}", s);
         //     self.write_result_to_addr(address, s.parse::<i32>().unwrap())
         // }
-        Ok(())
+        Some(input)
     }
 
-    fn display_value(&mut self, val: i64) {
-        // Display content at the address of index + 1
-        // println!("DISPLAYING {}", val);
+    pub fn display_value(&mut self, val: i64) {
         self.output.push(val);
+        self.notify(|o| o.output_produced(val));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_program() {
+        assert_eq!(parse_program("1,0,0,0,99\n").unwrap(), vec![1, 0, 0, 0, 99]);
+        assert_eq!(
+            parse_program("# add\n 1101, 2,-3 ,0,\n\n99 # stop\r\n").unwrap(),
+            vec![1101, 2, -3, 0, 99]
+        );
+        match parse_program("1,0,\n0,x0,99") {
+            Err(ProgramError::Invalid {
+                line,
+                column,
+                token,
+            }) => assert_eq!((line, column, &token[..]), (2, 3, "x0")),
+            r => panic!("unexpected {:?}", r),
+        }
+        match parse_program("1,,2") {
+            Err(ProgramError::Invalid { line, column, .. }) => assert_eq!((line, column), (1, 3)),
+            r => panic!("unexpected {:?}", r),
+        }
+        assert!(matches!(
+            parse_program(" # nothing\n"),
+            Err(ProgramError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_day2_profile() {
+        let mut i =
+            IntCode::with_instructions(vec![1, 0, 0, 0, 99], vec![], InstructionSet::day2());
+        assert_eq!(i.execute(), ExitCode::Stop);
+        assert_eq!(i.content, vec![2, 0, 0, 0, 99]);
+        let mut i =
+            IntCode::with_instructions(vec![1101, 1, 1, 0, 99], vec![], InstructionSet::day2());
+        assert_eq!(i.execute(), ExitCode::UnknowCode);
+        let mut i = IntCode::with_instructions(vec![104, 1, 99], vec![], InstructionSet::day2());
+        assert_eq!(i.execute(), ExitCode::UnknowCode);
+    }
+
+    #[test]
+    fn test_custom_opcode() {
+        use ParamKind::*;
+        let mut set = InstructionSet::full();
+        // Swap the values at two addresses
+        set.register(
+            20,
+            Instruction::new("swap", vec![Write, Write], |m, p| {
+                let (a, b) = (p[0] as usize, p[1] as usize);
+                m.content.swap(a, b);
+                Step::Next
+            }),
+        );
+        set.disable(2);
+        let mut i = IntCode::with_instructions(vec![20, 4, 5, 99, 7, 8], vec![], set);
+        assert_eq!(i.execute(), ExitCode::Stop);
+        assert_eq!(i.content, vec![20, 4, 5, 99, 8, 7]);
+        assert_eq!(i.instructions.get(2).map(|i| i.name), None);
+        assert_eq!(i.instructions.get(20).map(|i| i.name), Some("swap"));
+    }
+
+    #[test]
+    fn test_instruction_modes() {
+        use ParamKind::*;
+        let mut set = InstructionSet::full();
+        // Copy a value, without the relative mode
+        set.register(
+            21,
+            Instruction::new("copy", vec![Read, Write], |m, p| {
+                m.write_result_to_addr(p[1] as usize, p[0]);
+                Step::Next
+            })
+            .with_modes(&[ParamMode::Position, ParamMode::Immediate]),
+        );
+        let mut i = IntCode::with_instructions(vec![121, 7, 5, 99, 0, 0], vec![], set.clone());
+        assert_eq!(i.execute(), ExitCode::Stop);
+        assert_eq!(i.content[5], 7);
+        let mut i = IntCode::with_instructions(vec![221, 0, 5, 99, 0, 0], vec![], set.clone());
+        assert_eq!(i.execute(), ExitCode::UnknowCode);
+        // Add still accepts it
+        let mut i = IntCode::with_instructions(vec![22201, 5, 5, 5, 99, 3], vec![], set);
+        assert_eq!(i.execute(), ExitCode::Stop);
+        assert_eq!(i.content[5], 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "opcode 100 has more than two digits")]
+    fn test_register_out_of_range() {
+        let mut set = InstructionSet::empty();
+        set.register(
+            100,
+            Instruction::new("stop", vec![], |_, _| Step::Halt(ExitCode::Stop)),
+        );
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        executed: Vec<usize>,
+        reads: Vec<usize>,
+        writes: Vec<(usize, i64)>,
+        events: Vec<String>,
+    }
+
+    impl Observer for Recorder {
+        fn before_instruction(&mut self, machine: &IntCode) {
+            self.executed.push(machine.index);
+        }
+        fn memory_read(&mut self, addr: usize, _val: i64) {
+            self.reads.push(addr);
+        }
+        fn memory_write(&mut self, addr: usize, val: i64) {
+            self.writes.push((addr, val));
+        }
+        fn input_consumed(&mut self, val: i64) {
+            self.events.push(format!("in {}", val));
+        }
+        fn output_produced(&mut self, val: i64) {
+            self.events.push(format!("out {}", val));
+        }
+        fn halt(&mut self, code: &ExitCode) {
+            self.events.push(format!("{:?}", code));
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+        let mut i = IntCode::new(vec![3, 9, 1001, 9, 2, 9, 4, 9, 99, 0], vec![5]);
+        i.attach(recorder.clone());
+        assert_eq!(i.execute(), ExitCode::Output(7));
+        assert_eq!(i.execute(), ExitCode::Stop);
+        let r = recorder.borrow();
+        assert_eq!(r.executed, vec![0, 2, 6, 8]);
+        assert_eq!(r.reads, vec![9, 9]);
+        assert_eq!(r.writes, vec![(9, 5), (9, 7)]);
+        assert_eq!(r.events, vec!["in 5", "out 7", "Stop"]);
+    }
+
+    #[test]
+    fn test_faults() {
+        let run = |program: Vec<i64>| IntCode::new(program, vec![]).execute();
+        assert_eq!(run(vec![1, -1, 0, 0, 99]), ExitCode::InvalidAddress(-1));
+        assert_eq!(run(vec![1101, 1, 1, -5, 99]), ExitCode::InvalidAddress(-5));
+        assert_eq!(
+            run(vec![109, -3, 201, 0, 0, 0, 99]),
+            ExitCode::InvalidAddress(-3)
+        );
+        assert_eq!(
+            run(vec![1101, 1, 1, MAX_MEMORY as i64, 99]),
+            ExitCode::InvalidAddress(MAX_MEMORY as i64)
+        );
+        assert_eq!(run(vec![1105, 1, -2]), ExitCode::InvalidAddress(-2));
+        assert_eq!(run(vec![1101, i64::MAX, 1, 0, 99]), ExitCode::Overflow);
+        assert_eq!(run(vec![1102, i64::MIN, -1, 0, 99]), ExitCode::Overflow);
+        assert_eq!(run(vec![109, i64::MAX, 109, 1, 99]), ExitCode::Overflow);
+        // Running off the program, parameters included
+        assert_eq!(run(vec![1101, 1, 1, 0]), ExitCode::UnknowCode);
+        assert_eq!(run(vec![1101, 1]), ExitCode::UnknowCode);
+    }
+
+    #[test]
+    fn test_observer_await_input() {
+        let recorder = Rc::new(RefCell::new(Recorder::default()));
+        let counter = Rc::new(RefCell::new(Counter::default()));
+        let mut i = IntCode::new(vec![3, 5, 4, 5, 99, 0], vec![]);
+        i.attach(recorder.clone());
+        i.attach(counter.clone());
+        assert_eq!(i.execute(), ExitCode::AwaitInput);
+        assert_eq!(i.execute(), ExitCode::AwaitInput);
+        assert!(recorder.borrow().executed.is_empty());
+        i.push_input(8);
+        assert_eq!(i.execute(), ExitCode::Output(8));
+        assert_eq!(i.execute(), ExitCode::Stop);
+        let inputs = recorder
+            .borrow()
+            .executed
+            .iter()
+            .filter(|&&a| a == 0)
+            .count();
+        assert_eq!(inputs, 1);
+        assert_eq!(recorder.borrow().executed, vec![0, 2, 4]);
+        assert_eq!(counter.borrow().0, 3);
+    }
+
+    #[test]
+    fn test_tracer() {
+        let mut out = vec![];
+        {
+            let mut tracer = Tracer::new(&mut out);
+            let mut i = IntCode::new(vec![1101, 1, 2, 5, 1105], vec![]);
+            tracer.before_instruction(&i);
+            i.index = 4;
+            tracer.before_instruction(&i);
+            i.index = 9;
+            tracer.before_instruction(&i);
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "     0 add            [1101, 1, 2, 5]\n     4 jump-if-true   [1105]\n     9 unknown        []\n"
+        );
     }
 }
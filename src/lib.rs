@@ -1,21 +1,47 @@
+//! Advent of Code 2019 solutions, usable through the aoc-runner binary or
+//! as a library:
+//!
+//! ```
+//! assert_eq!(aoc19::solve(1, 1, "12\n14").unwrap().to_string(), "4");
+//! assert!(aoc19::days().contains(&9));
+//! ```
+//!
+//! Each day is also available on its own, e.g. `day9::input_generator` and
+//! `day9::part1`, and the IntCode computer lives in `intcode`.
+
 #[macro_use]
 extern crate aoc_runner_derive;
 
 use aoc_runner_derive::aoc_lib;
 
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-mod intcode;
+pub mod answers;
+pub mod bitmap;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day15;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod error;
+pub mod gif;
+pub mod grid;
+pub mod heatmap;
+pub mod image;
+pub mod intcode;
+pub mod ocr;
+pub mod registry;
+pub mod robot;
+pub mod sif;
+pub mod symbolic;
+
+pub use registry::{days, solutions, solve, Solution, SolveError};
 
 aoc_lib! { year = 2019 }
@@ -0,0 +1,150 @@
+//! Read the capital letters drawn by the puzzles, 6 pixels high and 4 wide
+//! (5 for `Y`) with an empty column between letters.
+
+use super::bitmap::Bitmap;
+use std::{error::Error, fmt};
+
+pub const HEIGHT: usize = 6;
+
+const GLYPHS: [(char, [&str; HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OcrError {
+    /// Letters are exactly 6 pixels high
+    Height(usize),
+    /// The glyph starting at this column, the letter being its index
+    UnknownGlyph {
+        letter: usize,
+        column: usize,
+    },
+    Blank,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::Height(h) => write!(f, "text is {} pixels high instead of {}", h, HEIGHT),
+            OcrError::UnknownGlyph { letter, column } => write!(
+                f,
+                "unknown glyph for letter {} at column {}",
+                letter, column
+            ),
+            OcrError::Blank => write!(f, "no text in the image"),
+        }
+    }
+}
+
+impl Error for OcrError {}
+
+fn blank_column(bitmap: &Bitmap, x: usize) -> bool {
+    (0..bitmap.height()).all(|y| !bitmap.lit(x, y))
+}
+
+/// Empty columns at the left of a glyph, like the two of `J`
+fn indent(glyph: &[&str; HEIGHT]) -> usize {
+    (0..glyph[0].len())
+        .take_while(|&i| glyph.iter().all(|row| row.as_bytes()[i] == b'.'))
+        .count()
+}
+
+/// Whether the glyph whose first lit column is `x` is drawn there
+fn matches(bitmap: &Bitmap, x: usize, glyph: &[&str; HEIGHT]) -> bool {
+    let width = glyph[0].len();
+    let x = match x.checked_sub(indent(glyph)) {
+        Some(x) => x,
+        None => return false,
+    };
+    x + width <= bitmap.width()
+        && blank_column(bitmap, x + width)
+        && glyph.iter().enumerate().all(|(y, row)| {
+            row.chars()
+                .enumerate()
+                .all(|(i, c)| bitmap.lit(x + i, y) == (c == '#'))
+        })
+}
+
+/// Text of the image, blank columns around the letters are ignored
+pub fn recognize(bitmap: &Bitmap) -> Result<String, OcrError> {
+    if bitmap.height() != HEIGHT {
+        return Err(OcrError::Height(bitmap.height()));
+    }
+    let mut text = String::new();
+    let mut x = 0;
+    while x < bitmap.width() {
+        if blank_column(bitmap, x) {
+            x += 1;
+            continue;
+        }
+        match GLYPHS.iter().find(|(_, glyph)| matches(bitmap, x, glyph)) {
+            Some((letter, glyph)) => {
+                text.push(*letter);
+                x += glyph[0].len() - indent(glyph);
+            }
+            None => {
+                return Err(OcrError::UnknownGlyph {
+                    letter: text.chars().count(),
+                    column: x,
+                })
+            }
+        }
+    }
+    if text.is_empty() {
+        return Err(OcrError::Blank);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn bitmap(letters: &[&[&str; HEIGHT]], gap: usize) -> Bitmap {
+        let rows: Vec<String> = (0..HEIGHT)
+            .map(|y| {
+                let gap = ".".repeat(gap);
+                let row: Vec<&str> = letters.iter().map(|l| l[y]).collect();
+                format!(".{}.", row.join(&gap))
+            })
+            .collect();
+        Bitmap::from_rows(&rows).unwrap()
+    }
+
+    #[test]
+    fn test_recognize() {
+        let all: Vec<&[&str; HEIGHT]> = GLYPHS.iter().map(|(_, g)| g).collect();
+        let text: String = GLYPHS.iter().map(|(l, _)| l).collect();
+        assert_eq!(recognize(&bitmap(&all, 1)), Ok(text.clone()));
+        assert_eq!(recognize(&bitmap(&all, 3)), Ok(text));
+
+        let unknown = ["####", "####", "####", "####", "####", "####"];
+        let letters = [&GLYPHS[0].1, &GLYPHS[1].1, &unknown];
+        assert_eq!(
+            recognize(&bitmap(&letters, 1)),
+            Err(OcrError::UnknownGlyph {
+                letter: 2,
+                column: 11
+            })
+        );
+        assert_eq!(recognize(&Bitmap::new(3, 6)), Err(OcrError::Blank));
+        assert_eq!(recognize(&Bitmap::new(3, 5)), Err(OcrError::Height(5)));
+    }
+}
@@ -0,0 +1,414 @@
+//! Every solution of the crate, runnable on any input without going through
+//! the aoc-runner binary.
+
+use super::bitmap::Bitmap;
+use super::ocr;
+use super::{
+    day1, day10, day11, day12, day13, day15, day2, day3, day4, day5, day6, day7, day8, day9,
+};
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// What a solution returns, images are printed as the text they read or as
+/// is when it cannot be read, see `Answer::line` for a one line form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    Image(Bitmap),
+}
+
+impl Answer {
+    /// Text read from an image answer
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Answer::Image(bitmap) => ocr::recognize(bitmap).ok(),
+            _ => None,
+        }
+    }
+
+    /// Form used to store and compare answers
+    pub fn line(&self) -> String {
+        match self {
+            Answer::Image(bitmap) => self.text().unwrap_or_else(|| bitmap.encode()),
+            answer => answer.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => n.fmt(f),
+            Answer::Text(s) => s.fmt(f),
+            Answer::Image(bitmap) => match self.text() {
+                Some(text) => text.fmt(f),
+                None => bitmap.fmt(f),
+            },
+        }
+    }
+}
+
+pub trait IntoAnswer {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>>;
+}
+
+macro_rules! number_answer {
+    ($($t:ty),*) => {
+        $(impl IntoAnswer for $t {
+            fn into_answer(self) -> Result<Answer, Box<dyn Error>> {
+                Ok(Answer::Number(self as i64))
+            }
+        })*
+    };
+}
+
+number_answer!(u32, usize, i64);
+
+impl IntoAnswer for String {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>> {
+        Ok(Answer::Text(self))
+    }
+}
+
+impl IntoAnswer for Answer {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>> {
+        Ok(self)
+    }
+}
+
+impl IntoAnswer for Bitmap {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>> {
+        Ok(Answer::Image(self))
+    }
+}
+
+impl<T: IntoAnswer, E: Error + 'static> IntoAnswer for Result<T, E> {
+    fn into_answer(self) -> Result<Answer, Box<dyn Error>> {
+        self?.into_answer()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub answer: Answer,
+    pub generator: Duration,
+    pub runner: Duration,
+}
+
+pub struct Solution {
+    pub day: u32,
+    pub part: u32,
+    pub name: Option<&'static str>,
+    run: fn(&str) -> Result<Outcome, Box<dyn Error>>,
+}
+
+impl Solution {
+    pub fn run(&self, input: &str) -> Result<Outcome, Box<dyn Error>> {
+        (self.run)(input)
+    }
+
+    /// Same label as the aoc-runner output, e.g. `Day 2 - Part 2 - BruteForce`
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(n) => format!("Day {} - Part {} - {}", self.day, self.part, n),
+            None => format!("Day {} - Part {}", self.day, self.part),
+        }
+    }
+}
+
+macro_rules! solution {
+    ($day:expr, $part:expr, $name:expr, $generator:path => $solver:path) => {
+        Solution {
+            day: $day,
+            part: $part,
+            name: $name,
+            run: |input| {
+                let start = Instant::now();
+                let input = $generator(input)?;
+                let generated = Instant::now();
+                let answer = $solver(&input).into_answer()?;
+                Ok(Outcome {
+                    answer,
+                    generator: generated - start,
+                    runner: generated.elapsed(),
+                })
+            },
+        }
+    };
+}
+
+/// All the solutions, sorted by day and part, the tests checking they are
+/// the ones declared with `#[aoc]` in the `dayN` modules for the days
+/// with an input
+pub fn solutions() -> Vec<Solution> {
+    vec![
+        solution!(1, 1, None, day1::input_generator => day1::part1),
+        solution!(1, 2, None, day1::input_generator => day1::part2),
+        solution!(2, 1, None, day2::input_generator => day2::part1),
+        solution!(2, 2, None, day2::input_generator => day2::part2),
+        solution!(2, 2, Some("BruteForce"), day2::input_generator => day2::part2_brute_force),
+        solution!(3, 1, None, day3::input_generator => day3::part1),
+        solution!(3, 2, None, day3::input_generator => day3::part2),
+        solution!(4, 1, None, day4::input_generator => day4::part1),
+        solution!(4, 2, None, day4::input_generator => day4::part2),
+        solution!(5, 1, None, day5::input_generator => day5::part1),
+        solution!(5, 2, None, day5::input_generator => day5::part2),
+        solution!(6, 1, None, day6::input_generator => day6::part1),
+        solution!(6, 2, None, day6::input_generator => day6::part2),
+        solution!(7, 1, None, day7::input_generator => day7::part1),
+        solution!(7, 2, None, day7::input_generator => day7::part2),
+        solution!(8, 1, None, day8::input_generator => day8::part1),
+        solution!(8, 2, None, day8::input_generator => day8::part2),
+        solution!(9, 1, None, day9::input_generator => day9::part1),
+        solution!(9, 2, None, day9::input_generator => day9::part2),
+        solution!(10, 1, None, day10::input_generator => day10::part1),
+        solution!(10, 2, None, day10::input_generator => day10::part2),
+        solution!(11, 1, None, day11::input_generator => day11::part1),
+        solution!(11, 2, None, day11::input_generator => day11::part2),
+        solution!(12, 1, None, day12::input_generator => day12::part1),
+        solution!(12, 2, None, day12::input_generator => day12::part2),
+        solution!(13, 1, None, day13::input_generator => day13::part1),
+        solution!(13, 2, None, day13::input_generator => day13::part2),
+        solution!(13, 2, Some("ScoreTable"), day13::input_generator => day13::part2_score_table),
+        solution!(15, 1, None, day15::input_generator => day15::part1),
+        solution!(15, 2, None, day15::input_generator => day15::part2),
+    ]
+}
+
+/// Days with at least one solution
+pub fn days() -> Vec<u32> {
+    let mut days: Vec<u32> = solutions().iter().map(|s| s.day).collect();
+    days.dedup();
+    days
+}
+
+/// The main solution of a part, the one without a name
+pub fn find(day: u32, part: u32) -> Option<Solution> {
+    solutions()
+        .into_iter()
+        .find(|s| s.day == day && s.part == part && s.name.is_none())
+}
+
+#[derive(Debug)]
+pub enum SolveError {
+    NotFound { day: u32, part: u32 },
+    Failed(Box<dyn Error>),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::NotFound { day, part } => {
+                write!(f, "no solution for day {} part {}", day, part)
+            }
+            SolveError::Failed(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for SolveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SolveError::NotFound { .. } => None,
+            SolveError::Failed(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+/// Solve a part of a day on the given input
+pub fn solve(day: u32, part: u32, input: &str) -> Result<Answer, SolveError> {
+    let solution = find(day, part).ok_or(SolveError::NotFound { day, part })?;
+    solution
+        .run(input)
+        .map(|o| o.answer)
+        .map_err(SolveError::Failed)
+}
+
+pub fn input_path(day: u32) -> PathBuf {
+    input_dir().join(format!("day{}.txt", day))
+}
+
+fn input_dir() -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "input",
+        &crate::YEAR.to_string(),
+    ]
+    .iter()
+    .collect()
+}
+
+/// Read an input file the way aoc-runner does, without the final line breaks
+fn read_input<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let input = fs::read_to_string(path)?;
+    Ok(input.trim_end_matches('\n').to_string())
+}
+
+/// The default input of a day
+pub fn load_input(day: u32) -> io::Result<String> {
+    read_input(input_path(day))
+}
+
+/// A puzzle input, either the default `dayN.txt` or a named one from the
+/// `dayN/` directory, e.g. `day1/alice.txt` is named `alice`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Input {
+    pub day: u32,
+    pub name: Option<String>,
+    pub path: PathBuf,
+}
+
+impl Input {
+    pub fn load(&self) -> io::Result<String> {
+        read_input(&self.path)
+    }
+}
+
+/// Every input of a day, the default one first then the named ones by name
+pub fn inputs(day: u32) -> io::Result<Vec<Input>> {
+    let mut inputs = vec![];
+    let path = input_path(day);
+    if path.is_file() {
+        inputs.push(Input {
+            day,
+            name: None,
+            path,
+        });
+    }
+    let dir = input_dir().join(format!("day{}", day));
+    if dir.is_dir() {
+        let mut named = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "txt") {
+                if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                    named.push(Input {
+                        day,
+                        name: Some(name.to_string()),
+                        path: path.clone(),
+                    });
+                }
+            }
+        }
+        named.sort_by(|a, b| a.name.cmp(&b.name));
+        inputs.extend(named);
+    }
+    Ok(inputs)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solutions() {
+        let solutions = solutions();
+        assert_eq!(solutions.len(), 30);
+        let labels: Vec<_> = solutions.iter().map(Solution::label).collect();
+        assert_eq!(labels[4], "Day 2 - Part 2 - BruteForce");
+
+        let day1 = &solutions[0];
+        assert_eq!(
+            day1.run("12\n14\n1969").unwrap().answer,
+            Answer::Number(658)
+        );
+        let day5 = &solutions[9];
+        assert_eq!(day5.run("3,0,4,0,99").unwrap().answer, Answer::Number(1));
+        assert!(day5.run("3,0,4,x,99").is_err());
+    }
+
+    #[test]
+    fn test_answer() {
+        let h = Bitmap::decode("#..#/#..#/####/#..#/#..#/#..#").unwrap();
+        let answer = Answer::Image(h);
+        assert_eq!(answer.text(), Some("H".to_string()));
+        assert_eq!(answer.line(), "H");
+        let blot = Bitmap::decode("##/##").unwrap();
+        let answer = Answer::Image(blot.clone());
+        assert_eq!(answer.text(), None);
+        assert_eq!(answer.line(), "##/##");
+        assert_eq!(answer.to_string(), blot.to_string());
+        assert_eq!(Answer::Number(3).line(), "3");
+    }
+
+    /// Labels of the `#[aoc]` attributes of the `dayN.rs` files, sorted
+    /// like the registry
+    /// Day number and source of every `dayN` module, sorted by day
+    fn day_sources() -> Vec<(u32, String)> {
+        let src: PathBuf = [env!("CARGO_MANIFEST_DIR"), "src"].iter().collect();
+        let mut sources = vec![];
+        for entry in fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("");
+            if !name.starts_with("day") || path.extension().is_none_or(|e| e != "rs") {
+                continue;
+            }
+            let day = name.trim_start_matches("day").parse().unwrap();
+            sources.push((day, fs::read_to_string(&path).unwrap()));
+        }
+        sources.sort_by_key(|(day, _)| *day);
+        sources
+    }
+
+    fn aoc_attributes() -> Vec<(u32, u32, Option<String>)> {
+        let mut labels = vec![];
+        for (_, source) in day_sources() {
+            for line in source.lines() {
+                let fields = match line.trim().strip_prefix("#[aoc(") {
+                    Some(rest) => rest.trim_end_matches(")]"),
+                    None => continue,
+                };
+                let fields: Vec<&str> = fields.split(',').map(str::trim).collect();
+                let day = fields[0].trim_start_matches("day").parse().unwrap();
+                let part = fields[1].trim_start_matches("part").parse().unwrap();
+                labels.push((day, part, fields.get(2).map(|n| n.to_string())));
+            }
+        }
+        // Unnamed solutions first, like aoc-runner prints them
+        labels.sort_by_key(|(day, part, name)| (*day, *part, name.is_some(), name.clone()));
+        labels
+    }
+
+    #[test]
+    fn test_registry_matches_sources() {
+        // aoc-runner embeds the input of every day it runs, the days without
+        // one are only in the registry
+        let registered: Vec<_> = solutions()
+            .iter()
+            .filter(|s| input_path(s.day).is_file())
+            .map(|s| (s.day, s.part, s.name.map(String::from)))
+            .collect();
+        assert_eq!(registered, aoc_attributes());
+    }
+
+    #[test]
+    fn test_solve() {
+        let modules: Vec<u32> = day_sources().iter().map(|(day, _)| *day).collect();
+        assert_eq!(days(), modules);
+        assert_eq!(solve(1, 2, "14\n1969").unwrap(), Answer::Number(968));
+        assert!(matches!(
+            solve(2, 3, "99"),
+            Err(SolveError::NotFound { day: 2, part: 3 })
+        ));
+        let e = solve(6, 1, "COM-B").unwrap_err();
+        assert!(matches!(e, SolveError::Failed(_)));
+        assert!(e.to_string().starts_with("day 6, line 1, column 1"));
+        let maze = include_str!("../tests/data/day15_maze.txt");
+        assert_eq!(solve(15, 1, maze).unwrap(), Answer::Number(3));
+        assert_eq!(solve(15, 2, maze).unwrap(), Answer::Number(4));
+    }
+
+    #[test]
+    fn test_inputs() {
+        let inputs = inputs(1).unwrap();
+        assert_eq!(inputs[0].name, None);
+        assert_eq!(inputs[0].load().unwrap(), load_input(1).unwrap());
+        assert_eq!(inputs[1].name.as_deref(), Some("example"));
+        assert_eq!(inputs[1].load().unwrap(), "12\n14\n1969\n100756");
+    }
+}
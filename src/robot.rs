@@ -0,0 +1,268 @@
+//! A robot driving on a grid of panels, `y` growing downwards like the rows
+//! of an image so that the panels render the way they are seen from above.
+//!
+//! A [`Runner`] lets an IntCode program drive the robot, the [`Protocol`]
+//! saying what the program is given when it reads an input and what the
+//! outputs of each action do.
+
+use super::grid::SparseGrid;
+use super::intcode::{ExitCode, IntCode};
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Clockwise from `Up`
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn turn(self, turn: Turn) -> Self {
+        let i = Direction::ALL.iter().position(|&d| d == self).unwrap_or(0);
+        match turn {
+            Turn::Right => Direction::ALL[(i + 1) % 4],
+            Turn::Left => Direction::ALL[(i + 3) % 4],
+        }
+    }
+
+    pub fn reverse(self) -> Self {
+        self.turn(Turn::Right).turn(Turn::Right)
+    }
+
+    /// Move of a single step
+    pub fn delta(self) -> (i64, i64) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+        }
+    }
+}
+
+/// Position and heading of a robot, with the panels it marked
+#[derive(Debug, Clone)]
+pub struct Robot<T> {
+    pub position: (i64, i64),
+    pub heading: Direction,
+    pub grid: SparseGrid<T>,
+}
+
+impl<T> Robot<T> {
+    /// At the origin of an empty grid
+    pub fn new(heading: Direction) -> Self {
+        Robot {
+            position: (0, 0),
+            heading,
+            grid: SparseGrid::new(),
+        }
+    }
+
+    pub fn turn(&mut self, turn: Turn) {
+        self.heading = self.heading.turn(turn);
+    }
+
+    /// One step forward
+    pub fn advance(&mut self) {
+        let (dx, dy) = self.heading.delta();
+        self.position = (self.position.0 + dx, self.position.1 + dy);
+    }
+
+    /// The panel under the robot, if marked
+    pub fn here(&self) -> Option<&T> {
+        self.grid.get(self.position.0, self.position.1)
+    }
+
+    /// Mark the panel under the robot
+    pub fn mark(&mut self, value: T) {
+        self.grid.set(self.position.0, self.position.1, value)
+    }
+}
+
+/// Whether the robot keeps following the program after an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// How a program senses and moves the robot
+pub trait Protocol {
+    type Cell;
+
+    /// Number of outputs making up an action
+    const ARITY: usize;
+
+    /// Input given to the program when it reads one
+    fn encode(&mut self, robot: &Robot<Self::Cell>) -> i64;
+
+    /// Apply an action, an error message rejecting outputs that make no sense
+    fn decode(&mut self, robot: &mut Robot<Self::Cell>, outputs: &[i64])
+        -> Result<Control, String>;
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RobotError {
+    /// The program failed, or stopped in the middle of an action
+    Aborted(ExitCode),
+    Action(String),
+}
+
+impl fmt::Display for RobotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RobotError::Aborted(code) => write!(f, "robot program aborted with {:?}", code),
+            RobotError::Action(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for RobotError {}
+
+/// A robot driven by an IntCode program
+pub struct Runner<P: Protocol> {
+    pub program: IntCode,
+    pub robot: Robot<P::Cell>,
+    pub protocol: P,
+}
+
+impl<P: Protocol> Runner<P> {
+    pub fn new(program: &[i64], robot: Robot<P::Cell>, protocol: P) -> Self {
+        Runner {
+            program: IntCode::new(program.to_vec(), vec![]),
+            robot,
+            protocol,
+        }
+    }
+
+    /// Until the program stops or the protocol stops the robot
+    pub fn run(&mut self) -> Result<(), RobotError> {
+        self.run_with(|_| {})
+    }
+
+    /// Same as `run`, `action` seeing the robot after each action
+    pub fn run_with<F: FnMut(&Robot<P::Cell>)>(&mut self, mut action: F) -> Result<(), RobotError> {
+        let mut outputs = Vec::with_capacity(P::ARITY);
+        loop {
+            match self.program.execute() {
+                ExitCode::Output(o) => {
+                    outputs.push(o);
+                    if outputs.len() == P::ARITY {
+                        let control = self
+                            .protocol
+                            .decode(&mut self.robot, &outputs)
+                            .map_err(RobotError::Action)?;
+                        outputs.clear();
+                        action(&self.robot);
+                        if control == Control::Stop {
+                            return Ok(());
+                        }
+                    }
+                }
+                ExitCode::AwaitInput if outputs.is_empty() => {
+                    let input = self.protocol.encode(&self.robot);
+                    self.program.push_input(input)
+                }
+                ExitCode::Stop if outputs.is_empty() => return Ok(()),
+                code => return Err(RobotError::Aborted(code)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Moves forward as long as the program echoes 1, turning right on 2
+    struct Walker {
+        actions: usize,
+    }
+
+    impl Protocol for Walker {
+        type Cell = usize;
+        const ARITY: usize = 1;
+
+        fn encode(&mut self, _robot: &Robot<usize>) -> i64 {
+            if self.actions == 2 {
+                2
+            } else {
+                1
+            }
+        }
+
+        fn decode(&mut self, robot: &mut Robot<usize>, outputs: &[i64]) -> Result<Control, String> {
+            match outputs {
+                [1] => robot.advance(),
+                [2] => robot.turn(Turn::Right),
+                _ => return Err(format!("unknown action {:?}", outputs)),
+            }
+            self.actions += 1;
+            robot.mark(self.actions);
+            Ok(if self.actions == 4 {
+                Control::Stop
+            } else {
+                Control::Continue
+            })
+        }
+    }
+
+    #[test]
+    fn test_robot() {
+        assert_eq!(Direction::Up.turn(Turn::Left), Direction::Left);
+        assert_eq!(Direction::Left.turn(Turn::Right), Direction::Up);
+        assert_eq!(Direction::Down.reverse(), Direction::Up);
+
+        let mut robot = Robot::new(Direction::Up);
+        robot.mark('a');
+        robot.advance();
+        robot.turn(Turn::Right);
+        robot.advance();
+        assert_eq!((robot.position, robot.heading), ((1, -1), Direction::Right));
+        assert_eq!(robot.here(), None);
+        robot.mark('b');
+        assert_eq!(robot.grid.render(|c| *c.unwrap_or(&'.')), vec![".b", "a."]);
+    }
+
+    #[test]
+    fn test_runner() {
+        // Echo every input
+        let echo = [3, 100, 4, 100, 1105, 1, 0];
+        let mut runner = Runner::new(&echo, Robot::new(Direction::Up), Walker { actions: 0 });
+        let mut seen = vec![];
+        runner.run_with(|robot| seen.push(robot.position)).unwrap();
+        assert_eq!(seen, vec![(0, -1), (0, -2), (0, -2), (1, -2)]);
+        assert_eq!(runner.robot.grid.len(), 3);
+
+        let mut runner = Runner::new(
+            &[104, 7, 99],
+            Robot::new(Direction::Up),
+            Walker { actions: 0 },
+        );
+        assert_eq!(
+            runner.run(),
+            Err(RobotError::Action("unknown action [7]".to_string()))
+        );
+        let mut runner = Runner::new(
+            &[3, 0, 99],
+            Robot::new(Direction::Up),
+            Walker { actions: 0 },
+        );
+        assert_eq!(runner.run(), Ok(()));
+    }
+}
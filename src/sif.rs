@@ -0,0 +1,310 @@
+//! Space Image Format: layers of digits of the same size, `0` being black,
+//! `1` white and `2` transparent, the first layer being in front.
+
+use super::bitmap::Bitmap;
+use super::grid::DenseGrid;
+use std::{error::Error, fmt};
+
+pub const BLACK: u8 = 0;
+pub const WHITE: u8 = 1;
+pub const TRANSPARENT: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SifError {
+    /// Not a digit, line and column starting at 1
+    Pixel {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    /// Not a whole number of layers, or none at all
+    Size {
+        pixels: usize,
+        width: usize,
+        height: usize,
+    },
+    /// A layer whose size differs from the first one
+    Layer { index: usize },
+    /// A pixel value which is not a digit
+    Value {
+        layer: usize,
+        x: usize,
+        y: usize,
+        value: u8,
+    },
+}
+
+impl fmt::Display for SifError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SifError::Pixel {
+                line,
+                column,
+                found,
+            } => write!(
+                f,
+                "invalid pixel {:?} at line {}, column {}",
+                found, line, column
+            ),
+            SifError::Size {
+                pixels,
+                width,
+                height,
+            } => write!(
+                f,
+                "{} pixels do not make {}x{} layers",
+                pixels, width, height
+            ),
+            SifError::Layer { index } => write!(f, "layer {} has a different size", index),
+            SifError::Value { layer, x, y, value } => write!(
+                f,
+                "pixel value {} at {},{} of layer {} is not a digit",
+                value, x, y, layer
+            ),
+        }
+    }
+}
+
+impl Error for SifError {}
+
+/// Number of pixels of each digit in a layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerStats {
+    counts: [usize; 10],
+}
+
+impl LayerStats {
+    pub fn count(&self, digit: u8) -> usize {
+        self.counts.get(digit as usize).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    layers: Vec<DenseGrid<u8>>,
+}
+
+impl Image {
+    /// Digits split in layers of `width` by `height` pixels, line breaks
+    /// being ignored
+    pub fn parse(data: &str, width: usize, height: usize) -> Result<Self, SifError> {
+        let mut pixels = vec![];
+        for (l, line) in data.lines().enumerate() {
+            for (c, p) in line.chars().enumerate() {
+                let pixel = p.to_digit(10).ok_or(SifError::Pixel {
+                    line: l + 1,
+                    column: c + 1,
+                    found: p,
+                })?;
+                pixels.push(pixel as u8);
+            }
+        }
+        let size = width * height;
+        if pixels.is_empty() || size == 0 || !pixels.len().is_multiple_of(size) {
+            return Err(SifError::Size {
+                pixels: pixels.len(),
+                width,
+                height,
+            });
+        }
+        Ok(Image {
+            width,
+            height,
+            layers: pixels
+                .chunks(size)
+                .map(|layer| DenseGrid::from_fn(width, height, |x, y| layer[y * width + x]))
+                .collect(),
+        })
+    }
+
+    /// Layers from front to back, all of the same size with digits as pixels
+    pub fn from_layers(layers: Vec<DenseGrid<u8>>) -> Result<Self, SifError> {
+        let (width, height) = match layers.first() {
+            Some(layer) => (layer.width(), layer.height()),
+            None => {
+                return Err(SifError::Size {
+                    pixels: 0,
+                    width: 0,
+                    height: 0,
+                })
+            }
+        };
+        if let Some(index) = layers
+            .iter()
+            .position(|l| (l.width(), l.height()) != (width, height))
+        {
+            return Err(SifError::Layer { index });
+        }
+        for (layer, grid) in layers.iter().enumerate() {
+            if let Some((x, y, &value)) = grid.iter().find(|&(_, _, &p)| p > 9) {
+                return Err(SifError::Value { layer, x, y, value });
+            }
+        }
+        Ok(Image {
+            width,
+            height,
+            layers,
+        })
+    }
+
+    /// One layer per bitmap drawing its lit pixels in white, over a black
+    /// background layer, so the image decodes to the union of the bitmaps
+    pub fn from_bitmaps(bitmaps: &[Bitmap]) -> Result<Self, SifError> {
+        let mut layers: Vec<DenseGrid<u8>> = bitmaps
+            .iter()
+            .map(|b| b.map(|&lit| if lit { WHITE } else { TRANSPARENT }))
+            .collect();
+        if let Some(first) = bitmaps.first() {
+            layers.push(DenseGrid::filled(first.width(), first.height(), BLACK));
+        }
+        Image::from_layers(layers)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// From front to back
+    pub fn layers(&self) -> &[DenseGrid<u8>] {
+        &self.layers
+    }
+
+    pub fn stats(&self) -> Vec<LayerStats> {
+        self.layers
+            .iter()
+            .map(|layer| {
+                let mut counts = [0; 10];
+                for (_, _, &p) in layer.iter() {
+                    counts[p as usize % 10] += 1;
+                }
+                LayerStats { counts }
+            })
+            .collect()
+    }
+
+    /// Number of `1` times number of `2` in the layer with the fewest `0`
+    pub fn checksum(&self) -> usize {
+        self.stats()
+            .iter()
+            .min_by_key(|s| s.count(0))
+            .map(|s| s.count(1) * s.count(2))
+            .unwrap_or(0)
+    }
+
+    /// The front most pixel which is not transparent, transparent when all
+    /// of them are
+    pub fn flatten(&self) -> DenseGrid<u8> {
+        DenseGrid::from_fn(self.width, self.height, |x, y| {
+            self.layers
+                .iter()
+                .map(|layer| layer[(x, y)])
+                .find(|&p| p != TRANSPARENT)
+                .unwrap_or(TRANSPARENT)
+        })
+    }
+
+    /// White pixels of the flattened image
+    pub fn decode(&self) -> Bitmap {
+        self.flatten().map(|&p| p == WHITE)
+    }
+
+    /// Digits of all the layers on a single line
+    pub fn encode(&self) -> String {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.iter().map(|(_, _, &p)| char::from(b'0' + p)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let image = Image::parse("123456789012", 3, 2).unwrap();
+        assert_eq!(image.layers().len(), 2);
+        assert_eq!(image.layers()[1][(0, 1)], 0);
+        assert_eq!(image.stats()[0].count(1), 1);
+        assert_eq!(image.checksum(), 1);
+        assert_eq!(image.encode(), "123456789012");
+
+        assert_eq!(
+            Image::parse("12345678901", 3, 2),
+            Err(SifError::Size {
+                pixels: 11,
+                width: 3,
+                height: 2
+            })
+        );
+        assert!(Image::parse("", 3, 2).is_err());
+        assert!(Image::parse("1234", 0, 2).is_err());
+        assert_eq!(
+            Image::parse("12\n3x", 2, 2),
+            Err(SifError::Pixel {
+                line: 2,
+                column: 2,
+                found: 'x'
+            })
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let image = Image::parse("0222112222120000", 2, 2).unwrap();
+        assert_eq!(
+            image.flatten().render(|&p| char::from(b'0' + p)),
+            vec!["01", "10"]
+        );
+        assert_eq!(image.decode(), Bitmap::from_rows(&[".#", "#."]).unwrap());
+        assert_eq!(
+            Image::parse("22", 2, 1).unwrap().flatten()[(0, 0)],
+            TRANSPARENT
+        );
+    }
+
+    #[test]
+    fn test_from_bitmaps() {
+        let a = Bitmap::from_rows(&["#..", "..."]).unwrap();
+        let b = Bitmap::from_rows(&["...", "..#"]).unwrap();
+        let image = Image::from_bitmaps(&[a.clone(), b]).unwrap();
+        assert_eq!(image.encode(), "122222222221000000");
+        let parsed = Image::parse(&image.encode(), 3, 2).unwrap();
+        assert_eq!(parsed, image);
+        assert_eq!(parsed.decode(), Bitmap::from_rows(&["#..", "..#"]).unwrap());
+        assert_eq!(
+            Image::from_bitmaps(std::slice::from_ref(&a))
+                .unwrap()
+                .decode(),
+            a
+        );
+
+        let small = Bitmap::from_rows(&["#"]).unwrap();
+        assert_eq!(
+            Image::from_bitmaps(&[a, small]),
+            Err(SifError::Layer { index: 1 })
+        );
+        assert!(Image::from_bitmaps(&[]).is_err());
+
+        let layers = vec![
+            DenseGrid::filled(2, 1, 9),
+            DenseGrid::from_fn(2, 1, |x, _| x as u8 * 250),
+        ];
+        assert_eq!(
+            Image::from_layers(layers),
+            Err(SifError::Value {
+                layer: 1,
+                x: 1,
+                y: 0,
+                value: 250
+            })
+        );
+    }
+}
@@ -0,0 +1,554 @@
+//! Symbolic execution of IntCode programs.
+//!
+//! Memory cells and inputs are replaced by symbols, the program is run on
+//! expressions instead of values and every conditional jump depending on a
+//! symbol forks the execution, recording the branch taken as a path
+//! constraint. Symbol values reaching a [`Goal`] are then searched by brute
+//! force: every value of the symbol domains is tried in turn, a constraint
+//! cutting the search as soon as the symbols it depends on are bound, so the
+//! domains should stay small. As in the IntCode VM, arithmetic overflows:
+//! a path only holds for the symbol values keeping every result in range.
+
+use std::{ops::Range, rc::Rc};
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Const(i64),
+    Var(usize),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mult(Rc<Expr>, Rc<Expr>),
+    LessThan(Rc<Expr>, Rc<Expr>),
+    Equals(Rc<Expr>, Rc<Expr>),
+    // Read through a symbolic pointer in a snapshot of the memory
+    Load(Rc<Expr>, Rc<Vec<Expr>>),
+}
+
+impl Expr {
+    fn as_const(&self) -> Option<i64> {
+        match self {
+            Expr::Const(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    // Constants are folded unless the result overflows, the expression then
+    // having no value
+    fn add(a: Expr, b: Expr) -> Expr {
+        match (a.as_const(), b.as_const()) {
+            (Some(x), Some(y)) if x.checked_add(y).is_some() => Expr::Const(x + y),
+            (Some(0), _) => b,
+            (_, Some(0)) => a,
+            _ => Expr::Add(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    fn mult(a: Expr, b: Expr) -> Expr {
+        match (a.as_const(), b.as_const()) {
+            (Some(x), Some(y)) if x.checked_mul(y).is_some() => Expr::Const(x * y),
+            (Some(0), _) | (_, Some(0)) => Expr::Const(0),
+            (Some(1), _) => b,
+            (_, Some(1)) => a,
+            _ => Expr::Mult(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    fn less_than(a: Expr, b: Expr) -> Expr {
+        match (a.as_const(), b.as_const()) {
+            (Some(x), Some(y)) => Expr::Const((x < y) as i64),
+            _ => Expr::LessThan(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    fn equals(a: Expr, b: Expr) -> Expr {
+        match (a.as_const(), b.as_const()) {
+            (Some(x), Some(y)) => Expr::Const((x == y) as i64),
+            _ => Expr::Equals(Rc::new(a), Rc::new(b)),
+        }
+    }
+
+    fn load(addr: Expr, memory: &Rc<Vec<Expr>>) -> Expr {
+        match addr.as_const() {
+            Some(a) if a >= 0 => memory.get(a as usize).cloned().unwrap_or(Expr::Const(0)),
+            _ => Expr::Load(Rc::new(addr), memory.clone()),
+        }
+    }
+
+    /// Evaluate the expression with the given symbol values, `None` if a
+    /// needed symbol is unbound, a pointer goes below address 0 or the
+    /// arithmetic overflows.
+    pub fn eval(&self, env: &[Option<i64>]) -> Option<i64> {
+        match self {
+            Expr::Const(c) => Some(*c),
+            Expr::Var(v) => env.get(*v).cloned().flatten(),
+            Expr::Add(a, b) => a.eval(env)?.checked_add(b.eval(env)?),
+            Expr::Mult(a, b) => a.eval(env)?.checked_mul(b.eval(env)?),
+            Expr::LessThan(a, b) => Some((a.eval(env)? < b.eval(env)?) as i64),
+            Expr::Equals(a, b) => Some((a.eval(env)? == b.eval(env)?) as i64),
+            Expr::Load(addr, memory) => match addr.eval(env)? {
+                a if a < 0 => None,
+                a => memory.get(a as usize).map_or(Some(0), |e| e.eval(env)),
+            },
+        }
+    }
+
+    fn max_var(&self) -> Option<usize> {
+        match self {
+            Expr::Const(_) => None,
+            Expr::Var(v) => Some(*v),
+            Expr::Add(a, b) | Expr::Mult(a, b) | Expr::LessThan(a, b) | Expr::Equals(a, b) => {
+                a.max_var().max(b.max_var())
+            }
+            Expr::Load(addr, memory) => memory
+                .iter()
+                .map(|e| e.max_var())
+                .fold(addr.max_var(), Option::max),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Constraint {
+    expr: Expr,
+    /// `None` only requires the expression to have a value, e.g. an
+    /// arithmetic result not to overflow
+    truthy: Option<bool>,
+}
+
+impl Constraint {
+    fn holds(&self, env: &[Option<i64>]) -> bool {
+        self.expr
+            .eval(env)
+            .is_some_and(|v| self.truthy.is_none_or(|t| (v != 0) == t))
+    }
+}
+
+fn assign(
+    var: usize,
+    domains: &[Range<i64>],
+    buckets: &[Vec<&Constraint>],
+    env: &mut Vec<Option<i64>>,
+) -> bool {
+    if var == domains.len() {
+        return true;
+    }
+    for value in domains[var].clone() {
+        env[var] = Some(value);
+        if buckets[var].iter().all(|c| c.holds(env)) && assign(var + 1, domains, buckets, env) {
+            return true;
+        }
+    }
+    env[var] = None;
+    false
+}
+
+// Brute force over the symbol domains, each constraint being checked as soon
+// as the last symbol it depends on is bound.
+fn solve(constraints: &[Constraint], domains: &[Range<i64>]) -> Option<Vec<i64>> {
+    let mut buckets = vec![vec![]; domains.len()];
+    for c in constraints {
+        match c.expr.max_var() {
+            Some(v) => buckets[v].push(c),
+            None if !c.holds(&[]) => return None,
+            None => {}
+        }
+    }
+    let mut env = vec![None; domains.len()];
+    if assign(0, domains, &buckets, &mut env) {
+        Some(env.into_iter().map(Option::unwrap).collect())
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SymbolicError {
+    SymbolicOpcode(usize),
+    SymbolicAddress(usize),
+    SymbolicJump(usize),
+    SymbolicRelativeBase(usize),
+    UnknownOpcode(usize, i64),
+    /// `max_steps` or `max_paths` stopped the search before every path was
+    /// explored
+    SearchLimit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Goal {
+    /// The instruction pointer reaches the address
+    Reach(usize),
+    /// The program outputs the value
+    Output(i64),
+    /// The program halts with the value at the address
+    Halt { address: usize, value: i64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symbol(usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Model {
+    values: Vec<i64>,
+    inputs: Vec<usize>,
+}
+
+impl Model {
+    pub fn value(&self, symbol: Symbol) -> i64 {
+        self.values[symbol.0]
+    }
+
+    /// Values to feed to the program inputs, in order
+    pub fn inputs(&self) -> Vec<i64> {
+        self.inputs.iter().map(|&v| self.values[v]).collect()
+    }
+}
+
+enum Event {
+    Continue,
+    Fork(Path),
+    Output(Expr),
+    Halt,
+    /// The arithmetic overflows whatever the symbol values
+    Overflow,
+}
+
+#[derive(Clone)]
+struct Path {
+    index: usize,
+    memory: Rc<Vec<Expr>>,
+    relative_base: i64,
+    domains: Vec<Range<i64>>,
+    inputs: Vec<usize>,
+    constraints: Vec<Constraint>,
+    steps: usize,
+}
+
+impl Path {
+    fn feasible(&self) -> bool {
+        solve(&self.constraints, &self.domains).is_some()
+    }
+
+    fn solve_with(&self, extra: Option<Constraint>) -> Option<Model> {
+        let mut constraints = self.constraints.clone();
+        constraints.extend(extra);
+        solve(&constraints, &self.domains).map(|values| Model {
+            values,
+            inputs: self.inputs.clone(),
+        })
+    }
+
+    fn read(&self, addr: usize) -> Expr {
+        self.memory.get(addr).cloned().unwrap_or(Expr::Const(0))
+    }
+
+    fn write(&mut self, addr: usize, val: Expr) {
+        let memory = Rc::make_mut(&mut self.memory);
+        if addr >= memory.len() {
+            memory.resize(addr + 1, Expr::Const(0));
+        }
+        memory[addr] = val;
+    }
+
+    fn param(&self, i: usize, mode: i64) -> Expr {
+        let raw = self.read(self.index + i);
+        match mode {
+            1 => raw,
+            2 => Expr::load(
+                Expr::add(Expr::Const(self.relative_base), raw),
+                &self.memory,
+            ),
+            _ => Expr::load(raw, &self.memory),
+        }
+    }
+
+    fn addr(&self, i: usize, mode: i64) -> Result<usize, SymbolicError> {
+        let raw = self.read(self.index + i);
+        let addr = match mode {
+            2 => Expr::add(Expr::Const(self.relative_base), raw),
+            _ => raw,
+        };
+        match addr.as_const() {
+            Some(a) if a >= 0 => Ok(a as usize),
+            _ => Err(SymbolicError::SymbolicAddress(self.index)),
+        }
+    }
+
+    fn step(&mut self, input_domain: &Range<i64>) -> Result<Event, SymbolicError> {
+        let at = self.index;
+        let opcode = self
+            .read(at)
+            .as_const()
+            .ok_or(SymbolicError::SymbolicOpcode(at))?;
+        let modes = [
+            (opcode / 100) % 10,
+            (opcode / 1000) % 10,
+            (opcode / 10000) % 10,
+        ];
+        self.steps += 1;
+        match opcode % 100 {
+            o @ 1 | o @ 2 | o @ 7 | o @ 8 => {
+                let val1 = self.param(1, modes[0]);
+                let val2 = self.param(2, modes[1]);
+                let addr = self.addr(3, modes[2])?;
+                let res = match o {
+                    1 => Expr::add(val1, val2),
+                    2 => Expr::mult(val1, val2),
+                    7 => Expr::less_than(val1, val2),
+                    _ => Expr::equals(val1, val2),
+                };
+                if (o == 1 || o == 2) && res.as_const().is_none() {
+                    if res.max_var().is_none() {
+                        return Ok(Event::Overflow);
+                    }
+                    self.constraints.push(Constraint {
+                        expr: res.clone(),
+                        truthy: None,
+                    });
+                }
+                self.write(addr, res);
+                self.index += 4;
+                Ok(Event::Continue)
+            }
+            3 => {
+                let addr = self.addr(1, modes[0])?;
+                let var = self.domains.len();
+                self.domains.push(input_domain.clone());
+                self.inputs.push(var);
+                self.write(addr, Expr::Var(var));
+                self.index += 2;
+                Ok(Event::Continue)
+            }
+            4 => {
+                let val1 = self.param(1, modes[0]);
+                self.index += 2;
+                Ok(Event::Output(val1))
+            }
+            o @ 5 | o @ 6 => {
+                let cond = self.param(1, modes[0]);
+                let target = self.param(2, modes[1]);
+                let truthy = o == 5;
+                let target = || match target.as_const() {
+                    Some(t) if t >= 0 => Ok(t as usize),
+                    _ => Err(SymbolicError::SymbolicJump(at)),
+                };
+                match cond.as_const() {
+                    Some(c) if (c != 0) == truthy => self.index = target()?,
+                    Some(_) => self.index += 3,
+                    None => {
+                        let mut other = self.clone();
+                        other.constraints.push(Constraint {
+                            expr: cond.clone(),
+                            truthy: Some(!truthy),
+                        });
+                        other.index += 3;
+                        self.constraints.push(Constraint {
+                            expr: cond,
+                            truthy: Some(truthy),
+                        });
+                        self.index = target()?;
+                        return Ok(Event::Fork(other));
+                    }
+                }
+                Ok(Event::Continue)
+            }
+            9 => {
+                let val1 = self.param(1, modes[0]);
+                let offset = val1
+                    .as_const()
+                    .ok_or(SymbolicError::SymbolicRelativeBase(at))?;
+                match self.relative_base.checked_add(offset) {
+                    Some(base) => self.relative_base = base,
+                    None => return Ok(Event::Overflow),
+                }
+                self.index += 2;
+                Ok(Event::Continue)
+            }
+            99 => Ok(Event::Halt),
+            _ => Err(SymbolicError::UnknownOpcode(at, opcode)),
+        }
+    }
+}
+
+pub struct Engine {
+    memory: Vec<Expr>,
+    domains: Vec<Range<i64>>,
+    input_domain: Range<i64>,
+    max_steps: usize,
+    max_paths: usize,
+}
+
+impl Engine {
+    pub fn new(program: &[i64]) -> Self {
+        Engine {
+            memory: program.iter().map(|&v| Expr::Const(v)).collect(),
+            domains: vec![],
+            input_domain: -1000..1000,
+            max_steps: 100_000,
+            max_paths: 1000,
+        }
+    }
+
+    /// Replace the memory cell at `address` by a symbol taking its value in `domain`
+    pub fn symbol_at(&mut self, address: usize, domain: Range<i64>) -> Symbol {
+        let symbol = Symbol(self.domains.len());
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, Expr::Const(0));
+        }
+        self.memory[address] = Expr::Var(symbol.0);
+        self.domains.push(domain);
+        symbol
+    }
+
+    /// Domain of the symbols created for each input instruction
+    pub fn input_domain(&mut self, domain: Range<i64>) {
+        self.input_domain = domain;
+    }
+
+    pub fn max_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps;
+    }
+
+    pub fn max_paths(&mut self, max_paths: usize) {
+        self.max_paths = max_paths;
+    }
+
+    /// Explore the program paths depth first until one of them can reach the
+    /// goal, `None` meaning that no symbol values reach it
+    pub fn solve(&self, goal: &Goal) -> Result<Option<Model>, SymbolicError> {
+        let mut paths = vec![Path {
+            index: 0,
+            memory: Rc::new(self.memory.clone()),
+            relative_base: 0,
+            domains: self.domains.clone(),
+            inputs: vec![],
+            constraints: vec![],
+            steps: 0,
+        }];
+        let mut explored = 0;
+        while let Some(mut path) = paths.pop() {
+            explored += 1;
+            if explored > self.max_paths {
+                return Err(SymbolicError::SearchLimit);
+            }
+            loop {
+                if path.steps >= self.max_steps {
+                    return Err(SymbolicError::SearchLimit);
+                }
+                if *goal == Goal::Reach(path.index) {
+                    if let Some(model) = path.solve_with(None) {
+                        return Ok(Some(model));
+                    }
+                }
+                match path.step(&self.input_domain)? {
+                    Event::Continue => {}
+                    Event::Fork(other) => {
+                        if other.feasible() {
+                            paths.push(other);
+                        }
+                        if !path.feasible() {
+                            break;
+                        }
+                    }
+                    Event::Output(val) => {
+                        if let Goal::Output(value) = *goal {
+                            let expr = Expr::equals(val, Expr::Const(value));
+                            if let Some(model) = path.solve_with(Some(Constraint {
+                                expr,
+                                truthy: Some(true),
+                            })) {
+                                return Ok(Some(model));
+                            }
+                        }
+                    }
+                    Event::Halt => {
+                        if let Goal::Halt { address, value } = *goal {
+                            let expr = Expr::equals(path.read(address), Expr::Const(value));
+                            if let Some(model) = path.solve_with(Some(Constraint {
+                                expr,
+                                truthy: Some(true),
+                            })) {
+                                return Ok(Some(model));
+                            }
+                        }
+                        break;
+                    }
+                    Event::Overflow => break,
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn program(input: &str) -> Vec<i64> {
+        input.split(',').map(|x| x.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_output_goal() {
+        // Output 1 if input equals 42 else 0
+        let engine = Engine::new(&program("3,9,8,9,10,9,4,9,99,-1,42"));
+        let model = engine.solve(&Goal::Output(1)).unwrap().unwrap();
+        assert_eq!(model.inputs(), vec![42]);
+    }
+
+    #[test]
+    fn test_reach_goal() {
+        // Output 7 only if the input is less than -5
+        let mut engine = Engine::new(&program("3,13,1007,13,-5,13,1005,13,10,99,104,7,99,0"));
+        engine.input_domain(-10..10);
+        let model = engine.solve(&Goal::Reach(10)).unwrap().unwrap();
+        assert!(model.inputs()[0] < -5);
+        assert_eq!(engine.solve(&Goal::Output(8)).unwrap(), None);
+        engine.max_paths(1);
+        assert_eq!(
+            engine.solve(&Goal::Output(8)),
+            Err(SymbolicError::SearchLimit)
+        );
+    }
+
+    #[test]
+    fn test_search_limit() {
+        // Loop forever
+        let mut engine = Engine::new(&program("1105,1,0"));
+        engine.max_steps(50);
+        assert_eq!(
+            engine.solve(&Goal::Output(0)),
+            Err(SymbolicError::SearchLimit)
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        // Multiply the cells at the noun and verb addresses, 3 times the
+        // value at 6 only reaching 22 by wrapping around
+        let mut engine = Engine::new(&program("2,0,0,0,99,3,-6148914691236517198,2,11"));
+        let noun = engine.symbol_at(1, 5..9);
+        let verb = engine.symbol_at(2, 5..9);
+        let goal = Goal::Halt {
+            address: 0,
+            value: 22,
+        };
+        let model = engine.solve(&goal).unwrap().unwrap();
+        assert_eq!((model.value(noun), model.value(verb)), (7, 8));
+        // Overflow whatever the symbol values
+        let engine = Engine::new(&program("2,7,7,0,104,0,99,4611686018427387904"));
+        assert_eq!(engine.solve(&Goal::Reach(4)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_halt_goal() {
+        let mut engine = Engine::new(&program("1,0,0,0,99,11,20"));
+        let noun = engine.symbol_at(1, 0..7);
+        let verb = engine.symbol_at(2, 0..7);
+        let goal = Goal::Halt {
+            address: 0,
+            value: 31,
+        };
+        let model = engine.solve(&goal).unwrap().unwrap();
+        assert_eq!((model.value(noun), model.value(verb)), (5, 6));
+    }
+}